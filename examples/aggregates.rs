@@ -1,14 +1,17 @@
 use chrono::NaiveDate;
 use futures::{StreamExt, TryStreamExt};
-use polygon::rest::{client, GetAggregate};
-use std::env;
+use polygon::config::Config;
+use polygon::rest::GetAggregate;
 use stream_flatten_iters::TryStreamExt as _;
 
 #[tokio::main]
 async fn main() {
     env_logger::init();
-    let key = env::var("POLYGON_TOKEN").unwrap();
-    let client = client(&key);
+    let mut config = Config::from_env().unwrap();
+    // Both requests share the same rate limit, so the free plan's 5 requests per minute are
+    // respected even though the requests are paginated in parallel.
+    config.requests_per_minute.get_or_insert(5);
+    let client = config.throttled_client().unwrap();
     let req1 = GetAggregate::new(
         "GE",
         NaiveDate::from_ymd(2011, 11, 5).and_hms(0, 0, 0),
@@ -0,0 +1,33 @@
+use chrono::NaiveDate;
+use futures::StreamExt;
+use polygon::config::Config;
+use polygon::rest::{DownloadKind, Downloader, PartitionStatus, Timespan};
+
+#[tokio::main]
+async fn main() {
+    env_logger::init();
+    let mut config = Config::from_env().unwrap();
+    config.requests_per_minute.get_or_insert(5);
+    let client = config.throttled_client().unwrap();
+    let downloader = Downloader::new(client, "data");
+    let kind = DownloadKind::Aggregates {
+        multiplier: 1,
+        timespan: Timespan::Minute,
+    };
+    downloader
+        .download(
+            &["AAPL", "MSFT", "GE"],
+            NaiveDate::from_ymd_opt(2021, 11, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2021, 11, 5).unwrap(),
+            kind,
+        )
+        .await
+        .unwrap()
+        .for_each(|p| async move {
+            if let PartitionStatus::Failed(e) = &p.status {
+                eprintln!("{} {}: {}", p.partition.ticker, p.partition.date, e);
+            }
+            println!("{}/{}", p.completed, p.total);
+        })
+        .await;
+}
@@ -1,13 +1,12 @@
 use chrono::NaiveDate;
 use futures::{StreamExt, TryStreamExt};
-use polygon::rest::{client, GetQuotes};
-use std::env;
+use polygon::config::Config;
+use polygon::rest::GetQuotes;
 use stream_flatten_iters::TryStreamExt as _;
 
 #[tokio::main]
 async fn main() {
-    let key = env::var("POLYGON_TOKEN").unwrap();
-    let client = client(&key);
+    let client = Config::from_env().unwrap().throttled_client().unwrap();
     let req = GetQuotes::new("GE", NaiveDate::from_ymd(2021, 11, 5)).limit(50000);
 
     client
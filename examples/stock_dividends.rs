@@ -1,13 +1,12 @@
-use polygon::rest::{client, GetStockDividends};
-use std::env;
+use polygon::config::Config;
+use polygon::rest::GetStockDividends;
 
 #[tokio::main]
 async fn main() {
     env_logger::init();
-    let key = env::var("POLYGON_TOKEN").unwrap();
-    let client = client(&key);
+    let client = Config::from_env().unwrap().throttled_client().unwrap();
     let req = GetStockDividends {
-        stocks_ticker: "AAPL".to_string(),
+        stocks_ticker: "AAPL".into(),
     };
 
     println!("{:#?}", client.send(&req).await);
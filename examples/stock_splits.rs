@@ -1,13 +1,12 @@
-use polygon::rest::{client, GetStockSplits};
-use std::env;
+use polygon::config::Config;
+use polygon::rest::GetStockSplits;
 
 #[tokio::main]
 async fn main() {
     env_logger::init();
-    let key = env::var("POLYGON_TOKEN").unwrap();
-    let client = client(&key);
+    let client = Config::from_env().unwrap().throttled_client().unwrap();
     let req = GetStockSplits {
-        stocks_ticker: "AAPL".to_string(),
+        stocks_ticker: "AAPL".into(),
     };
 
     println!("{:#?}", client.send(&req).await);
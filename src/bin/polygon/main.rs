@@ -0,0 +1,450 @@
+//! Command-line access to the Polygon REST and WebSocket APIs.
+
+mod output;
+
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use clap::error::ErrorKind;
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use futures::prelude::*;
+use output::{print_line, print_records, print_values, Format};
+use polygon::errors::Result;
+use polygon::export::{CsvMessageWriter, CsvOptions, NdjsonWriter};
+use polygon::rest::{
+    Aggregate, AggregateWrapper, AssetClass, DataType, GetAggregate, GetConditions, GetDividends,
+    GetEma, GetExchanges, GetFinancials, GetLastQuote, GetLastTrade, GetMacd, GetMarketHolidays,
+    GetMarketStatus, GetPreviousClose, GetQuotes, GetRsi, GetSma, GetSplits, GetTickerDetails,
+    GetTickerNews, GetTickerSnapshot, GetTickerTypes, Locale, Quote, QuoteWrapper, ThrottledClient,
+    Timeframe, Timespan,
+};
+use polygon::ws::{Connection, PolygonMessage};
+use serde::de::DeserializeOwned;
+
+#[derive(Parser)]
+#[command(name = "polygon", version, about = "Query market data from Polygon")]
+struct Cli {
+    /// The Polygon API key
+    #[arg(long, env = "POLYGON_TOKEN", hide_env_values = true)]
+    token: String,
+    #[arg(long, short, value_enum, default_value = "table", global = true)]
+    format: Format,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Aggregate bars for a ticker
+    Aggs {
+        ticker: String,
+        /// The first day of the range
+        #[arg(long)]
+        from: NaiveDate,
+        /// The last day of the range, inclusive
+        #[arg(long)]
+        to: NaiveDate,
+        #[arg(long, default_value = "day", value_parser = parse_value::<Timespan>)]
+        timespan: Timespan,
+        #[arg(long, default_value_t = 1)]
+        multiplier: u32,
+        #[arg(long)]
+        unadjusted: bool,
+        /// The timezone that days start and end in, e.g. UTC for crypto and forex tickers
+        #[arg(long, default_value = "America/New_York")]
+        timezone: Tz,
+    },
+    /// NBBO quotes for a ticker on a single day
+    Quotes {
+        ticker: String,
+        #[arg(long)]
+        date: NaiveDate,
+        /// The number of quotes to request per page
+        #[arg(long, default_value_t = 50000)]
+        limit: u32,
+    },
+    /// The previous day's bar for a ticker
+    PrevClose {
+        ticker: String,
+        #[arg(long)]
+        unadjusted: bool,
+    },
+    /// The current snapshot of a ticker
+    Snapshot { ticker: String },
+    /// The most recent trade for a ticker
+    LastTrade { ticker: String },
+    /// The most recent NBBO quote for a ticker
+    LastQuote { ticker: String },
+    /// Recent news articles, optionally about a single ticker
+    News {
+        ticker: Option<String>,
+        #[arg(long, default_value_t = 10)]
+        limit: u32,
+    },
+    /// Financial statements from a company's filings
+    Financials {
+        ticker: String,
+        #[arg(long, value_parser = parse_value::<Timeframe>)]
+        timeframe: Option<Timeframe>,
+        #[arg(long, default_value_t = 10)]
+        limit: u32,
+    },
+    /// A technical indicator computed by Polygon
+    Indicator {
+        #[command(subcommand)]
+        indicator: Indicator,
+    },
+    /// Stock splits for a ticker
+    Splits { ticker: String },
+    /// Cash dividends for a ticker
+    Dividends { ticker: String },
+    /// Reference details for a ticker
+    Details { ticker: String },
+    /// The current status of the markets
+    Status,
+    /// Upcoming market holidays
+    Holidays,
+    /// The exchanges and other venues known to Polygon
+    Exchanges {
+        #[arg(long, value_parser = parse_value::<AssetClass>)]
+        asset_class: Option<AssetClass>,
+    },
+    /// The conditions that can be attached to trades and quotes
+    Conditions {
+        #[arg(long, value_parser = parse_value::<AssetClass>)]
+        asset_class: Option<AssetClass>,
+        #[arg(long, value_parser = parse_value::<DataType>)]
+        data_type: Option<DataType>,
+    },
+    /// The types of tickers, e.g. CS for common stock
+    TickerTypes {
+        #[arg(long, value_parser = parse_value::<AssetClass>)]
+        asset_class: Option<AssetClass>,
+        #[arg(long, value_parser = parse_value::<Locale>)]
+        locale: Option<Locale>,
+    },
+    /// Print live messages from the WebSocket API
+    Stream {
+        /// The tickers to subscribe to
+        #[arg(required = true)]
+        tickers: Vec<String>,
+        /// The events to subscribe to, e.g. T for trades, Q for quotes and AM for minute
+        /// aggregates. CSV output takes events of a single kind, since each kind has its own
+        /// columns.
+        #[arg(long, value_delimiter = ',', default_value = "T,Q")]
+        events: Vec<String>,
+        #[arg(long, default_value = "wss://socket.polygon.io/stocks")]
+        url: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum Indicator {
+    /// Simple moving average
+    Sma {
+        #[command(flatten)]
+        args: IndicatorArgs,
+        #[arg(long, default_value_t = 50)]
+        window: u32,
+    },
+    /// Exponential moving average
+    Ema {
+        #[command(flatten)]
+        args: IndicatorArgs,
+        #[arg(long, default_value_t = 50)]
+        window: u32,
+    },
+    /// Relative strength index
+    Rsi {
+        #[command(flatten)]
+        args: IndicatorArgs,
+        #[arg(long, default_value_t = 14)]
+        window: u32,
+    },
+    /// Moving average convergence/divergence
+    Macd {
+        #[command(flatten)]
+        args: IndicatorArgs,
+        #[arg(long, default_value_t = 12)]
+        short_window: u32,
+        #[arg(long, default_value_t = 26)]
+        long_window: u32,
+        #[arg(long, default_value_t = 9)]
+        signal_window: u32,
+    },
+}
+
+#[derive(Args)]
+struct IndicatorArgs {
+    ticker: String,
+    #[arg(long, default_value = "day", value_parser = parse_value::<Timespan>)]
+    timespan: Timespan,
+    /// The number of values to request, starting from the most recent
+    #[arg(long, default_value_t = 10)]
+    limit: u32,
+}
+
+/// The kinds of WebSocket messages that are written as CSV, each with its own columns.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CsvKind {
+    Trades,
+    Quotes,
+    Aggregates,
+}
+
+impl CsvKind {
+    fn of(event: &str) -> Option<Self> {
+        match event {
+            "T" => Some(CsvKind::Trades),
+            "Q" => Some(CsvKind::Quotes),
+            "A" | "AM" => Some(CsvKind::Aggregates),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a value by its serialized name, ignoring case, e.g. `day` or `quarterly`.
+fn parse_value<T: DeserializeOwned>(s: &str) -> std::result::Result<T, String> {
+    serde_json::from_value(serde_json::Value::String(s.to_lowercase()))
+        .map_err(|_| format!("invalid value: {}", s))
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    if let Err(e) = run(cli).await {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    let client = ThrottledClient::builder(&cli.token).build()?;
+    let format = cli.format;
+    match cli.command {
+        Command::Aggs {
+            ticker,
+            from,
+            to,
+            timespan,
+            multiplier,
+            unadjusted,
+            timezone,
+        } => {
+            let req = GetAggregate::from_dates(ticker, from, to)
+                .timezone(timezone)
+                .timespan(timespan)
+                .multiplier(multiplier)
+                .unadjusted(unadjusted);
+            let pages: Vec<AggregateWrapper> = client.send_paginated(&req).try_collect().await?;
+            let aggs: Vec<Aggregate> = pages.into_iter().flat_map(|p| p.results).collect();
+            print_records(format, &aggs)
+        }
+        Command::Quotes {
+            ticker,
+            date,
+            limit,
+        } => {
+            let req = GetQuotes::new(ticker, date).limit(limit);
+            let pages: Vec<QuoteWrapper> = client.send_paginated(&req).try_collect().await?;
+            let quotes: Vec<Quote> = pages.into_iter().flat_map(|p| p.results).collect();
+            print_records(format, &quotes)
+        }
+        Command::PrevClose { ticker, unadjusted } => {
+            let req = GetPreviousClose::new(ticker).unadjusted(unadjusted);
+            print_values(format, &client.send(&req).await?.results)
+        }
+        Command::Snapshot { ticker } => {
+            let snapshot = client.send(&GetTickerSnapshot::new(ticker)).await?;
+            print_values(format, &[snapshot.ticker])
+        }
+        Command::LastTrade { ticker } => {
+            let trade = client.send(&GetLastTrade::new(ticker)).await?;
+            print_values(format, &[trade.results])
+        }
+        Command::LastQuote { ticker } => {
+            let quote = client.send(&GetLastQuote::new(ticker)).await?;
+            print_values(format, &[quote.results])
+        }
+        Command::News { ticker, limit } => {
+            let mut req = GetTickerNews::new().limit(limit);
+            if let Some(ticker) = ticker {
+                req = req.ticker(ticker);
+            }
+            print_values(format, &client.send(&req).await?.results)
+        }
+        Command::Financials {
+            ticker,
+            timeframe,
+            limit,
+        } => {
+            let mut req = GetFinancials::new().ticker(ticker).limit(limit);
+            if let Some(timeframe) = timeframe {
+                req = req.timeframe(timeframe);
+            }
+            print_values(format, &client.send(&req).await?.results)
+        }
+        Command::Indicator { indicator } => indicator_values(&client, format, indicator).await,
+        Command::Splits { ticker } => {
+            let req = GetSplits::new().ticker(ticker);
+            let pages: Vec<_> = client.send_paginated(&req).try_collect().await?;
+            let splits: Vec<_> = pages.into_iter().flat_map(|p| p.results).collect();
+            print_values(format, &splits)
+        }
+        Command::Dividends { ticker } => {
+            let req = GetDividends::new().ticker(ticker);
+            let pages: Vec<_> = client.send_paginated(&req).try_collect().await?;
+            let dividends: Vec<_> = pages.into_iter().flat_map(|p| p.results).collect();
+            print_values(format, &dividends)
+        }
+        Command::Details { ticker } => {
+            let details = client.send(&GetTickerDetails::new(ticker)).await?;
+            print_values(format, &[details.results])
+        }
+        Command::Status => print_values(format, &[client.send(&GetMarketStatus).await?]),
+        Command::Holidays => print_values(format, &client.send(&GetMarketHolidays).await?),
+        Command::Exchanges { asset_class } => {
+            let mut req = GetExchanges::new();
+            if let Some(asset_class) = asset_class {
+                req = req.asset_class(asset_class);
+            }
+            print_values(format, &client.send(&req).await?.results)
+        }
+        Command::Conditions {
+            asset_class,
+            data_type,
+        } => {
+            let mut req = GetConditions::new();
+            if let Some(asset_class) = asset_class {
+                req = req.asset_class(asset_class);
+            }
+            if let Some(data_type) = data_type {
+                req = req.data_type(data_type);
+            }
+            let pages: Vec<_> = client.send_paginated(&req).try_collect().await?;
+            let conditions: Vec<_> = pages.into_iter().flat_map(|p| p.results).collect();
+            print_values(format, &conditions)
+        }
+        Command::TickerTypes {
+            asset_class,
+            locale,
+        } => {
+            let mut req = GetTickerTypes::new();
+            if let Some(asset_class) = asset_class {
+                req = req.asset_class(asset_class);
+            }
+            if let Some(locale) = locale {
+                req = req.locale(locale);
+            }
+            print_values(format, &client.send(&req).await?.results)
+        }
+        Command::Stream {
+            tickers,
+            events,
+            url,
+        } => stream(&cli.token, format, &url, &events, &tickers).await,
+    }
+}
+
+async fn indicator_values(
+    client: &ThrottledClient,
+    format: Format,
+    indicator: Indicator,
+) -> Result<()> {
+    match indicator {
+        Indicator::Sma { args, window } => {
+            let req = GetSma::new(args.ticker)
+                .timespan(args.timespan)
+                .limit(args.limit)
+                .window(window);
+            print_values(format, &client.send(&req).await?.results.values)
+        }
+        Indicator::Ema { args, window } => {
+            let req = GetEma::new(args.ticker)
+                .timespan(args.timespan)
+                .limit(args.limit)
+                .window(window);
+            print_values(format, &client.send(&req).await?.results.values)
+        }
+        Indicator::Rsi { args, window } => {
+            let req = GetRsi::new(args.ticker)
+                .timespan(args.timespan)
+                .limit(args.limit)
+                .window(window);
+            print_values(format, &client.send(&req).await?.results.values)
+        }
+        Indicator::Macd {
+            args,
+            short_window,
+            long_window,
+            signal_window,
+        } => {
+            let req = GetMacd::new(args.ticker)
+                .timespan(args.timespan)
+                .limit(args.limit)
+                .short_window(short_window)
+                .long_window(long_window)
+                .signal_window(signal_window);
+            print_values(format, &client.send(&req).await?.results.values)
+        }
+    }
+}
+
+/// Print messages as they arrive. Table output prints one line per message, while CSV output
+/// writes a single header, so it only takes events of one kind.
+async fn stream(
+    token: &str,
+    format: Format,
+    url: &str,
+    events: &[String],
+    tickers: &[String],
+) -> Result<()> {
+    let csv_kind = match events
+        .iter()
+        .map(|e| CsvKind::of(e))
+        .collect::<Option<Vec<_>>>()
+    {
+        Some(kinds) if kinds.windows(2).all(|k| k[0] == k[1]) => kinds.first().copied(),
+        _ => None,
+    };
+    if format == Format::Csv && csv_kind.is_none() {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "CSV output needs events of a single kind: T, Q, or A and AM",
+            )
+            .exit();
+    }
+    let ws = Connection::new(url, token, events, tickers)
+        .messages()
+        .await?;
+    match format {
+        Format::Json => {
+            NdjsonWriter::new(std::io::stdout())
+                .write_messages(ws)
+                .await
+        }
+        Format::Csv => {
+            let writer = CsvMessageWriter::new(CsvOptions::default());
+            let mut writer = match csv_kind {
+                Some(CsvKind::Trades) => writer.trades(std::io::stdout()),
+                Some(CsvKind::Quotes) => writer.quotes(std::io::stdout()),
+                _ => writer.aggregates(std::io::stdout()),
+            };
+            let mut ws = ws;
+            while let Some(message) = ws.next().await {
+                writer.write(&message?)?;
+                writer.flush()?;
+            }
+            Ok(())
+        }
+        Format::Table => {
+            ws.try_for_each(|message| async move {
+                match message {
+                    PolygonMessage::Status { .. } => Ok(()),
+                    message => print_line(&message),
+                }
+            })
+            .await
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use clap::ValueEnum;
+use polygon::errors::Result;
+use polygon::export::{CsvOptions, CsvRecord, CsvWriter};
+use serde::Serialize;
+use serde_json::Value;
+use std::io::Write;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Table,
+    Json,
+    Csv,
+}
+
+/// Rows flattened into string columns, for table and CSV output.
+struct Rows {
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Rows {
+    fn from_records<T: CsvRecord>(records: &[T]) -> Self {
+        let options = CsvOptions::default();
+        Self {
+            header: T::header().iter().map(|c| c.to_string()).collect(),
+            rows: records.iter().map(|r| r.to_fields(&options)).collect(),
+        }
+    }
+
+    /// Flatten arbitrary values, naming nested fields with dotted paths, e.g. `day.o`.
+    fn from_values<T: Serialize>(values: &[T]) -> Result<Self> {
+        let flattened: Vec<Vec<(String, String)>> = values
+            .iter()
+            .map(|v| {
+                let mut fields = Vec::new();
+                flatten("", &to_value(v)?, &mut fields);
+                Ok(fields)
+            })
+            .collect::<Result<_>>()?;
+        let mut header: Vec<String> = Vec::new();
+        for (column, _) in flattened.iter().flatten() {
+            if !header.contains(column) {
+                header.push(column.clone());
+            }
+        }
+        let rows = flattened
+            .into_iter()
+            .map(|fields| {
+                header
+                    .iter()
+                    .map(|column| {
+                        fields
+                            .iter()
+                            .find(|(c, _)| c == column)
+                            .map(|(_, v)| v.clone())
+                            .unwrap_or_default()
+                    })
+                    .collect()
+            })
+            .collect();
+        Ok(Self { header, rows })
+    }
+
+    fn write_table<W: Write>(&self, mut out: W) -> Result<()> {
+        let mut widths: Vec<usize> = self.header.iter().map(|c| c.len()).collect();
+        for row in &self.rows {
+            for (width, field) in widths.iter_mut().zip(row) {
+                *width = (*width).max(field.chars().count());
+            }
+        }
+        for row in std::iter::once(&self.header).chain(&self.rows) {
+            let line: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(field, width)| format!("{:width$}", field, width = width))
+                .collect();
+            writeln!(out, "{}", line.join("  ").trim_end())?;
+        }
+        Ok(())
+    }
+
+    fn write_csv<W: Write>(&self, out: W) -> Result<()> {
+        let mut writer = csv::Writer::from_writer(out);
+        writer.write_record(&self.header)?;
+        for row in &self.rows {
+            writer.write_record(row)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+fn to_value<T: Serialize>(value: &T) -> Result<Value> {
+    serde_json::to_value(value).map_err(|error| polygon::errors::Error::Serde {
+        error,
+        msg: "Failed to format output".into(),
+    })
+}
+
+fn flatten(prefix: &str, value: &Value, fields: &mut Vec<(String, String)>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let column = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten(&column, value, fields);
+            }
+        }
+        // Lists such as conditions are joined like the `export` CSV columns
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(scalar).collect();
+            fields.push((prefix.to_string(), items.join(";")));
+        }
+        _ => fields.push((prefix.to_string(), scalar(value))),
+    }
+}
+
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn write_json<T: Serialize, W: Write>(values: &T, mut out: W) -> Result<()> {
+    let json =
+        serde_json::to_string_pretty(values).map_err(|error| polygon::errors::Error::Serde {
+            error,
+            msg: "Failed to format output".into(),
+        })?;
+    writeln!(out, "{}", json)?;
+    Ok(())
+}
+
+/// Print rows that have a fixed column order in the `export` CSV format.
+pub fn print_records<T: CsvRecord + Serialize>(format: Format, records: &[T]) -> Result<()> {
+    let out = std::io::stdout();
+    let out = out.lock();
+    match format {
+        Format::Json => write_json(&records, out),
+        Format::Table => Rows::from_records(records).write_table(out),
+        Format::Csv => {
+            let mut writer = CsvWriter::new(out, CsvOptions::default());
+            for record in records {
+                writer.write(record)?;
+            }
+            writer.flush()
+        }
+    }
+}
+
+/// Print any other values, flattening nested fields into columns.
+pub fn print_values<T: Serialize>(format: Format, values: &[T]) -> Result<()> {
+    let out = std::io::stdout();
+    let out = out.lock();
+    match format {
+        Format::Json => write_json(&values, out),
+        Format::Table => Rows::from_values(values)?.write_table(out),
+        Format::Csv => Rows::from_values(values)?.write_csv(out),
+    }
+}
+
+/// Print a value on a single line as `field=value` pairs.
+pub fn print_line<T: Serialize>(value: &T) -> Result<()> {
+    let mut fields = Vec::new();
+    flatten("", &to_value(value)?, &mut fields);
+    let line: Vec<String> = fields
+        .into_iter()
+        .map(|(column, value)| format!("{}={}", column, value))
+        .collect();
+    println!("{}", line.join(" "));
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn flattens_nested_values() {
+        let values = vec![
+            serde_json::json!({"ticker": "AAPL", "day": {"o": 1.5, "c": 2}, "c": [12, 37]}),
+            serde_json::json!({"ticker": "MSFT", "day": {"o": 3}, "c": null}),
+        ];
+        let rows = Rows::from_values(&values).unwrap();
+        assert_eq!(rows.header, vec!["c", "day.c", "day.o", "ticker"]);
+        assert_eq!(rows.rows[0], vec!["12;37", "2", "1.5", "AAPL"]);
+        assert_eq!(rows.rows[1], vec!["", "", "3", "MSFT"]);
+
+        let mut table = Vec::new();
+        rows.write_table(&mut table).unwrap();
+        assert_eq!(
+            String::from_utf8(table).unwrap(),
+            "c      day.c  day.o  ticker\n12;37  2      1.5    AAPL\n              3      MSFT\n"
+        );
+    }
+}
@@ -0,0 +1,188 @@
+//! A single entry point to the REST and WebSocket APIs.
+
+#[cfg(feature = "rest")]
+use crate::common::Ticker;
+use crate::config::Config;
+use crate::errors::Result;
+#[cfg(feature = "rest")]
+use crate::rest::{
+    Aggregate, GetAggregate, GetLastQuote, GetLastTrade, GetMarketStatus, GetQuotes, GetTickRange,
+    GetTickerDetails, GetTickerSnapshot, LastQuote, LastTrade, MarketEvent, MarketStatus, Quote,
+    ThrottledClient, TickRequest, TickerDetails, TickerSnapshot, WatchMarket,
+};
+#[cfg(feature = "rest")]
+use futures::prelude::*;
+use std::fmt;
+
+/// A client for the whole Polygon API, which owns its configuration.
+///
+/// REST requests share the rate limit, retries and timeouts of the config, and paginated
+/// endpoints are returned as streams of results.
+///
+/// ```no_run
+/// # async fn run() -> polygon::errors::Result<()> {
+/// use futures::TryStreamExt;
+/// use polygon::rest::GetAggregate;
+/// use polygon::Polygon;
+///
+/// let polygon = Polygon::from_env()?;
+/// let from = chrono::NaiveDate::from_ymd_opt(2021, 11, 1).unwrap();
+/// let to = chrono::NaiveDate::from_ymd_opt(2021, 11, 5).unwrap();
+/// let aggs: Vec<_> = polygon
+///     .aggregates(GetAggregate::from_dates("AAPL", from, to))
+///     .try_collect()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Polygon {
+    config: Config,
+    #[cfg(feature = "rest")]
+    client: ThrottledClient,
+}
+
+impl fmt::Debug for Polygon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Polygon")
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Polygon {
+    pub fn new<T: ToString>(api_key: T) -> Result<Self> {
+        Self::from_config(Config::new(api_key))
+    }
+
+    /// Create a client from a config, which fails if the config's rate limit is zero.
+    pub fn from_config(config: Config) -> Result<Self> {
+        Ok(Self {
+            #[cfg(feature = "rest")]
+            client: config.throttled_client()?,
+            config,
+        })
+    }
+
+    /// Create a client from [`Config::from_env`].
+    pub fn from_env() -> Result<Self> {
+        Self::from_config(Config::from_env()?)
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+}
+
+#[cfg(feature = "rest")]
+impl Polygon {
+    /// Stream the aggregates in the request's range, following pagination.
+    pub fn aggregates(
+        &self,
+        request: GetAggregate,
+    ) -> impl Stream<Item = Result<Aggregate>> + Unpin + '_ {
+        self.client
+            .into_paginated(request)
+            .map_ok(|page| stream::iter(page.results.into_iter().map(Ok)))
+            .try_flatten()
+    }
+
+    /// Stream the quotes for the request's day, following pagination.
+    pub fn quotes(&self, request: GetQuotes) -> impl Stream<Item = Result<Quote>> + Unpin + '_ {
+        self.client
+            .into_paginated(request)
+            .map_ok(|page| stream::iter(page.results.into_iter().map(Ok)))
+            .try_flatten()
+    }
+
+    /// Stream the quotes or trades over a range of trading days, following pagination.
+    pub fn ticks<R>(
+        &self,
+        request: &GetTickRange<R>,
+    ) -> impl Stream<Item = Result<R::Tick>> + Unpin + '_
+    where
+        R: TickRequest + 'static,
+    {
+        self.client.send_tick_range(request)
+    }
+
+    pub async fn snapshot<T: Into<Ticker>>(&self, ticker: T) -> Result<TickerSnapshot> {
+        Ok(self
+            .client
+            .send(&GetTickerSnapshot::new(ticker))
+            .await?
+            .ticker)
+    }
+
+    pub async fn last_trade<T: Into<Ticker>>(&self, ticker: T) -> Result<LastTrade> {
+        Ok(self.client.send(&GetLastTrade::new(ticker)).await?.results)
+    }
+
+    pub async fn last_quote<T: Into<Ticker>>(&self, ticker: T) -> Result<LastQuote> {
+        Ok(self.client.send(&GetLastQuote::new(ticker)).await?.results)
+    }
+
+    pub async fn ticker_details<T: Into<Ticker>>(&self, ticker: T) -> Result<TickerDetails> {
+        Ok(self
+            .client
+            .send(&GetTickerDetails::new(ticker))
+            .await?
+            .results)
+    }
+
+    pub async fn market_status(&self) -> Result<MarketStatus> {
+        self.client.send(&GetMarketStatus).await
+    }
+
+    /// Stream the transitions between trading sessions, such as the regular open and close.
+    pub fn market_events(
+        &self,
+        watch: &WatchMarket,
+    ) -> impl Stream<Item = Result<MarketEvent>> + Unpin + '_ {
+        self.client.watch_market(watch)
+    }
+}
+
+#[cfg(feature = "ws")]
+impl Polygon {
+    /// Connect to the configured WebSocket feed and subscribe to `events` for `tickers`.
+    pub async fn stream<E: ToString, T: ToString>(
+        &self,
+        events: &[E],
+        tickers: &[T],
+    ) -> Result<crate::ws::MessageStream> {
+        self.config.connection(events, tickers).messages().await
+    }
+}
+
+#[cfg(all(test, feature = "rest"))]
+mod test {
+    use super::*;
+    use chrono::NaiveDate;
+    use mockito::{mock, Matcher};
+
+    #[tokio::test]
+    async fn streams_paginated_results() {
+        let _m = mock(
+            "GET",
+            Matcher::Regex(r"^/v2/aggs/ticker/MSFT/range/1/day/".into()),
+        )
+        .match_query(Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()))
+        .with_body(r#"{"ticker":"MSFT","status":"OK","queryCount":2,"resultsCount":2,"adjusted":true,"results":[{"v":23732158,"vw":235.7741,"o":235.9,"c":236.94,"h":237.47,"l":233.15,"t":1614574800000,"n":266440},{"v":25324474,"vw":234.3286,"o":237.01,"c":233.87,"h":237.3,"l":233.45,"t":1614661200000,"n":263624}],"request_id":"a1b2c3"}"#)
+        .create();
+
+        let mut config = Config::new("TOKEN");
+        config.base_url = mockito::server_url();
+        let polygon = Polygon::from_config(config).unwrap();
+        let from = NaiveDate::from_ymd_opt(2021, 3, 1).unwrap();
+        let to = NaiveDate::from_ymd_opt(2021, 3, 2).unwrap();
+        let req = GetAggregate::new(
+            "MSFT",
+            from.and_hms_opt(0, 0, 0).unwrap(),
+            to.and_hms_opt(23, 59, 59).unwrap(),
+        );
+        let aggs: Vec<Aggregate> = polygon.aggregates(req).try_collect().await.unwrap();
+        assert_eq!(aggs.len(), 2);
+        assert_eq!(aggs[1].n, Some(263624));
+    }
+}
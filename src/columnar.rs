@@ -0,0 +1,485 @@
+//! Conversion of market data into Arrow record batches, and writing them as Parquet files.
+//!
+//! Timestamps are stored as `timestamp[ns, tz=UTC]` and prices as `decimal128(38, 10)`, so the
+//! files can be loaded directly into Polars or pandas without any further conversion.
+
+use crate::common::Ticker;
+use crate::errors::{Error, Result};
+use arrow::array::{
+    ArrayRef, Decimal128Array, ListArray, StringArray, TimestampNanosecondArray, UInt32Array,
+    UInt64Array, UInt8Array,
+};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit, UInt32Type, UInt8Type};
+use arrow::record_batch::RecordBatch;
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::US::Eastern;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// The scale of all decimal columns.
+pub const DECIMAL_SCALE: i8 = 10;
+const DECIMAL_PRECISION: u8 = 38;
+
+/// Market data that can be converted into an Arrow [`RecordBatch`].
+pub trait Columnar: Sized {
+    fn schema() -> SchemaRef;
+
+    fn to_record_batch(rows: &[Self]) -> Result<RecordBatch>;
+
+    /// The time of the row, which determines the date partition it is written to.
+    fn timestamp(&self) -> DateTime<Utc>;
+}
+
+fn timestamp_field(name: &str, nullable: bool) -> Field {
+    Field::new(
+        name,
+        DataType::Timestamp(TimeUnit::Nanosecond, Some("UTC".into())),
+        nullable,
+    )
+}
+
+fn decimal_field(name: &str) -> Field {
+    Field::new(
+        name,
+        DataType::Decimal128(DECIMAL_PRECISION, DECIMAL_SCALE),
+        true,
+    )
+}
+
+fn list_field(name: &str, item: DataType) -> Field {
+    Field::new(
+        name,
+        DataType::List(Arc::new(Field::new("item", item, true))),
+        true,
+    )
+}
+
+fn timestamps<I: IntoIterator<Item = Option<DateTime<Utc>>>>(iter: I) -> ArrayRef {
+    let array: TimestampNanosecondArray = iter
+        .into_iter()
+        .map(|t| t.and_then(|t| t.timestamp_nanos_opt()))
+        .collect();
+    Arc::new(array.with_timezone("UTC"))
+}
+
+/// The value at [`DECIMAL_SCALE`], or an error if it's too large to be rescaled.
+fn mantissa(value: Decimal) -> Result<i128> {
+    let mut rescaled = value;
+    rescaled.rescale(DECIMAL_SCALE as u32);
+    if rescaled.scale() == DECIMAL_SCALE as u32 {
+        Ok(rescaled.mantissa())
+    } else {
+        Err(Error::DecimalOverflow(value))
+    }
+}
+
+fn decimals<I: IntoIterator<Item = Option<Decimal>>>(iter: I) -> Result<ArrayRef> {
+    let array: Decimal128Array = iter
+        .into_iter()
+        .map(|d| d.map(mantissa).transpose())
+        .collect::<Result<_>>()?;
+    Ok(Arc::new(array.with_precision_and_scale(
+        DECIMAL_PRECISION,
+        DECIMAL_SCALE,
+    )?))
+}
+
+fn strings<'a, I: IntoIterator<Item = Option<&'a str>>>(iter: I) -> ArrayRef {
+    Arc::new(iter.into_iter().collect::<StringArray>())
+}
+
+fn u8s<I: IntoIterator<Item = Option<u8>>>(iter: I) -> ArrayRef {
+    Arc::new(iter.into_iter().collect::<UInt8Array>())
+}
+
+fn u32s<I: IntoIterator<Item = Option<u32>>>(iter: I) -> ArrayRef {
+    Arc::new(iter.into_iter().collect::<UInt32Array>())
+}
+
+fn u64s<I: IntoIterator<Item = Option<u64>>>(iter: I) -> ArrayRef {
+    Arc::new(iter.into_iter().collect::<UInt64Array>())
+}
+
+fn batch(schema: SchemaRef, columns: Vec<ArrayRef>) -> Result<RecordBatch> {
+    Ok(RecordBatch::try_new(schema, columns)?)
+}
+
+#[cfg(feature = "rest")]
+mod rest_types {
+    use super::*;
+    use crate::rest::{Aggregate, AggregateSnapshot, Quote, TickerSnapshot};
+
+    impl Columnar for Aggregate {
+        fn schema() -> SchemaRef {
+            Arc::new(Schema::new(vec![
+                timestamp_field("timestamp", false),
+                decimal_field("open"),
+                decimal_field("high"),
+                decimal_field("low"),
+                decimal_field("close"),
+                decimal_field("volume"),
+                decimal_field("vwap"),
+                Field::new("transactions", DataType::UInt32, true),
+            ]))
+        }
+
+        fn to_record_batch(rows: &[Self]) -> Result<RecordBatch> {
+            batch(
+                Self::schema(),
+                vec![
+                    timestamps(rows.iter().map(|r| Some(r.t))),
+                    decimals(rows.iter().map(|r| Some(r.o)))?,
+                    decimals(rows.iter().map(|r| Some(r.h)))?,
+                    decimals(rows.iter().map(|r| Some(r.l)))?,
+                    decimals(rows.iter().map(|r| Some(r.c)))?,
+                    decimals(rows.iter().map(|r| Some(r.v)))?,
+                    decimals(rows.iter().map(|r| r.vw))?,
+                    u32s(rows.iter().map(|r| r.n)),
+                ],
+            )
+        }
+
+        fn timestamp(&self) -> DateTime<Utc> {
+            self.t
+        }
+    }
+
+    impl Columnar for Quote {
+        fn schema() -> SchemaRef {
+            Arc::new(Schema::new(vec![
+                timestamp_field("sip_timestamp", false),
+                timestamp_field("participant_timestamp", false),
+                timestamp_field("trf_timestamp", true),
+                Field::new("sequence_number", DataType::UInt32, false),
+                list_field("conditions", DataType::UInt32),
+                list_field("indicators", DataType::UInt32),
+                decimal_field("bid_price"),
+                Field::new("bid_exchange", DataType::UInt32, false),
+                Field::new("bid_size", DataType::UInt32, false),
+                decimal_field("ask_price"),
+                Field::new("ask_exchange", DataType::UInt32, false),
+                Field::new("ask_size", DataType::UInt32, false),
+                Field::new("tape", DataType::UInt8, false),
+            ]))
+        }
+
+        fn to_record_batch(rows: &[Self]) -> Result<RecordBatch> {
+            let conditions = ListArray::from_iter_primitive::<UInt32Type, _, _>(
+                rows.iter()
+                    .map(|r| Some(r.c.iter().map(|&c| Some(c)).collect::<Vec<_>>())),
+            );
+            let indicators =
+                ListArray::from_iter_primitive::<UInt32Type, _, _>(rows.iter().map(|r| {
+                    r.i.as_ref()
+                        .map(|i| i.iter().map(|&i| Some(i)).collect::<Vec<_>>())
+                }));
+            batch(
+                Self::schema(),
+                vec![
+                    timestamps(rows.iter().map(|r| Some(r.t))),
+                    timestamps(rows.iter().map(|r| Some(r.y))),
+                    timestamps(rows.iter().map(|r| r.f)),
+                    u32s(rows.iter().map(|r| Some(r.q))),
+                    Arc::new(conditions),
+                    Arc::new(indicators),
+                    decimals(rows.iter().map(|r| Some(r.bid_price)))?,
+                    u32s(rows.iter().map(|r| Some(r.bid_exchange))),
+                    u32s(rows.iter().map(|r| Some(r.bid_size))),
+                    decimals(rows.iter().map(|r| Some(r.ask_price)))?,
+                    u32s(rows.iter().map(|r| Some(r.ask_exchange))),
+                    u32s(rows.iter().map(|r| Some(r.ask_size))),
+                    u8s(rows.iter().map(|r| Some(r.z))),
+                ],
+            )
+        }
+
+        fn timestamp(&self) -> DateTime<Utc> {
+            self.t
+        }
+    }
+
+    fn snapshot_fields(prefix: &str) -> Vec<Field> {
+        vec![
+            decimal_field(&format!("{}_open", prefix)),
+            decimal_field(&format!("{}_high", prefix)),
+            decimal_field(&format!("{}_low", prefix)),
+            decimal_field(&format!("{}_close", prefix)),
+            Field::new(format!("{}_volume", prefix), DataType::UInt64, false),
+            decimal_field(&format!("{}_vwap", prefix)),
+        ]
+    }
+
+    fn snapshot_columns<'a, F>(rows: &'a [TickerSnapshot], f: F) -> Result<Vec<ArrayRef>>
+    where
+        F: Fn(&'a TickerSnapshot) -> &'a AggregateSnapshot,
+    {
+        Ok(vec![
+            decimals(rows.iter().map(|r| Some(f(r).o)))?,
+            decimals(rows.iter().map(|r| Some(f(r).h)))?,
+            decimals(rows.iter().map(|r| Some(f(r).l)))?,
+            decimals(rows.iter().map(|r| Some(f(r).c)))?,
+            u64s(rows.iter().map(|r| Some(f(r).v))),
+            decimals(rows.iter().map(|r| Some(f(r).vw)))?,
+        ])
+    }
+
+    /// Snapshots are flattened into one column per field, with the day, minute and previous day
+    /// bars prefixed by `day_`, `minute_` and `previous_day_`.
+    impl Columnar for TickerSnapshot {
+        fn schema() -> SchemaRef {
+            let mut fields = vec![
+                Field::new("ticker", DataType::Utf8, false),
+                timestamp_field("updated", false),
+                decimal_field("todays_change"),
+                decimal_field("todays_change_percent"),
+            ];
+            fields.extend(snapshot_fields("day"));
+            fields.extend(snapshot_fields("minute"));
+            fields.extend(snapshot_fields("previous_day"));
+            fields.extend(vec![
+                decimal_field("bid_price"),
+                Field::new("bid_size", DataType::UInt32, false),
+                decimal_field("ask_price"),
+                Field::new("ask_size", DataType::UInt32, false),
+                timestamp_field("quote_timestamp", false),
+                decimal_field("last_price"),
+                Field::new("last_size", DataType::UInt32, false),
+                Field::new("last_exchange", DataType::UInt8, false),
+                timestamp_field("trade_timestamp", false),
+            ]);
+            Arc::new(Schema::new(fields))
+        }
+
+        fn to_record_batch(rows: &[Self]) -> Result<RecordBatch> {
+            let mut columns = vec![
+                strings(rows.iter().map(|r| Some(r.ticker.as_str()))),
+                timestamps(rows.iter().map(|r| Some(r.updated))),
+                decimals(rows.iter().map(|r| Some(r.todays_change)))?,
+                decimals(rows.iter().map(|r| Some(r.todays_change_percent)))?,
+            ];
+            columns.extend(snapshot_columns(rows, |r| &r.day)?);
+            columns.extend(snapshot_columns(rows, |r| &r.minute)?);
+            columns.extend(snapshot_columns(rows, |r| &r.previous_day)?);
+            columns.extend(vec![
+                decimals(rows.iter().map(|r| Some(r.last_quote.bid_price)))?,
+                u32s(rows.iter().map(|r| Some(r.last_quote.bid_size))),
+                decimals(rows.iter().map(|r| Some(r.last_quote.ask_price)))?,
+                u32s(rows.iter().map(|r| Some(r.last_quote.ask_size))),
+                timestamps(rows.iter().map(|r| Some(r.last_quote.t))),
+                decimals(rows.iter().map(|r| Some(r.last_trade.p)))?,
+                u32s(rows.iter().map(|r| Some(r.last_trade.s))),
+                u8s(rows.iter().map(|r| Some(r.last_trade.x))),
+                timestamps(rows.iter().map(|r| Some(r.last_trade.t))),
+            ]);
+            batch(Self::schema(), columns)
+        }
+
+        fn timestamp(&self) -> DateTime<Utc> {
+            self.updated
+        }
+    }
+}
+
+#[cfg(feature = "ws")]
+mod ws_types {
+    use super::*;
+    use crate::ws::{Quote, Trade};
+
+    impl Columnar for Trade {
+        fn schema() -> SchemaRef {
+            Arc::new(Schema::new(vec![
+                Field::new("symbol", DataType::Utf8, false),
+                timestamp_field("timestamp", false),
+                Field::new("exchange_id", DataType::UInt8, false),
+                Field::new("trade_id", DataType::Utf8, false),
+                Field::new("tape", DataType::UInt8, false),
+                decimal_field("price"),
+                Field::new("size", DataType::UInt32, false),
+                list_field("conditions", DataType::UInt8),
+            ]))
+        }
+
+        fn to_record_batch(rows: &[Self]) -> Result<RecordBatch> {
+            let conditions =
+                ListArray::from_iter_primitive::<UInt8Type, _, _>(rows.iter().map(|r| {
+                    Some(
+                        r.conditions
+                            .iter()
+                            .map(|&c| Some(c as u8))
+                            .collect::<Vec<_>>(),
+                    )
+                }));
+            batch(
+                Self::schema(),
+                vec![
+                    strings(rows.iter().map(|r| Some(r.symbol.as_str()))),
+                    timestamps(rows.iter().map(|r| Some(r.timestamp))),
+                    u8s(rows.iter().map(|r| Some(r.exchange_id))),
+                    strings(rows.iter().map(|r| Some(r.trade_id.as_str()))),
+                    u8s(rows.iter().map(|r| Some(r.tape.clone() as u8))),
+                    decimals(rows.iter().map(|r| Some(r.price)))?,
+                    u32s(rows.iter().map(|r| Some(r.size))),
+                    Arc::new(conditions),
+                ],
+            )
+        }
+
+        fn timestamp(&self) -> DateTime<Utc> {
+            self.timestamp
+        }
+    }
+
+    /// The bid and ask columns are null for quotes that only update the other side.
+    impl Columnar for Quote {
+        fn schema() -> SchemaRef {
+            Arc::new(Schema::new(vec![
+                Field::new("symbol", DataType::Utf8, false),
+                timestamp_field("timestamp", false),
+                Field::new("bid_exchange", DataType::UInt8, true),
+                decimal_field("bid_price"),
+                Field::new("bid_size", DataType::UInt32, true),
+                Field::new("ask_exchange", DataType::UInt8, true),
+                decimal_field("ask_price"),
+                Field::new("ask_size", DataType::UInt32, true),
+                Field::new("condition", DataType::UInt8, true),
+            ]))
+        }
+
+        fn to_record_batch(rows: &[Self]) -> Result<RecordBatch> {
+            batch(
+                Self::schema(),
+                vec![
+                    strings(rows.iter().map(|r| Some(r.symbol.as_str()))),
+                    timestamps(rows.iter().map(|r| Some(r.timestamp))),
+                    u8s(rows
+                        .iter()
+                        .map(|r| r.bid_quote.as_ref().map(|q| q.exchange_id))),
+                    decimals(rows.iter().map(|r| r.bid_quote.as_ref().map(|q| q.price)))?,
+                    u32s(rows.iter().map(|r| r.bid_quote.as_ref().map(|q| q.size))),
+                    u8s(rows
+                        .iter()
+                        .map(|r| r.ask_quote.as_ref().map(|q| q.exchange_id))),
+                    decimals(rows.iter().map(|r| r.ask_quote.as_ref().map(|q| q.price)))?,
+                    u32s(rows.iter().map(|r| r.ask_quote.as_ref().map(|q| q.size))),
+                    u8s(rows.iter().map(|r| r.condition.clone().map(|c| c as u8))),
+                ],
+            )
+        }
+
+        fn timestamp(&self) -> DateTime<Utc> {
+            self.timestamp
+        }
+    }
+}
+
+/// Write the rows to a single Snappy-compressed Parquet file.
+pub fn write_parquet<T: Columnar, P: AsRef<Path>>(path: P, rows: &[T]) -> Result<()> {
+    let batch = T::to_record_batch(rows)?;
+    let props = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let file = File::create(path)?;
+    let mut writer = ArrowWriter::try_new(file, batch.schema(), Some(props))?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(())
+}
+
+/// Write the rows for a ticker as Parquet files partitioned by the US/Eastern date of each row,
+/// using the hive-style layout `<dir>/ticker=<ticker>/date=<date>/data.parquet`, with the ticker
+/// named by [`Ticker::file_name`]. Existing partitions are overwritten. Returns the paths of the written files.
+pub fn write_partitioned<T, P>(dir: P, ticker: &str, rows: &[T]) -> Result<Vec<PathBuf>>
+where
+    T: Columnar + Clone,
+    P: AsRef<Path>,
+{
+    let mut partitions: BTreeMap<NaiveDate, Vec<T>> = BTreeMap::new();
+    for row in rows {
+        let date = row.timestamp().with_timezone(&Eastern).date_naive();
+        partitions.entry(date).or_default().push(row.clone());
+    }
+    partitions
+        .into_iter()
+        .map(|(date, rows)| {
+            let partition = dir
+                .as_ref()
+                .join(format!("ticker={}", Ticker::from(ticker).file_name()))
+                .join(format!("date={}", date));
+            std::fs::create_dir_all(&partition)?;
+            let path = partition.join("data.parquet");
+            write_parquet(&path, &rows)?;
+            Ok(path)
+        })
+        .collect()
+}
+
+#[cfg(all(test, feature = "rest"))]
+mod test {
+    use super::*;
+    use crate::rest::Aggregate;
+    use arrow::array::Array;
+    use chrono::{Duration, TimeZone};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn aggregates_to_parquet() {
+        let start = Utc.with_ymd_and_hms(2021, 3, 1, 20, 59, 0).unwrap();
+        let rows: Vec<Aggregate> = (0..3)
+            .map(|i| Aggregate {
+                o: dec!(120.25),
+                h: dec!(121),
+                l: dec!(119.5),
+                c: dec!(120.7512),
+                v: dec!(1000),
+                vw: (i != 1).then(|| dec!(120.3)),
+                t: start + Duration::hours(5 * i),
+                n: Some(12),
+            })
+            .collect();
+
+        let batch = Aggregate::to_record_batch(&rows).unwrap();
+        let close = batch
+            .column(4)
+            .as_any()
+            .downcast_ref::<Decimal128Array>()
+            .unwrap();
+        assert_eq!(close.value_as_string(0), "120.7512000000");
+        assert!(batch.column(6).is_null(1));
+
+        let mut huge = rows[..1].to_vec();
+        huge[0].v = Decimal::MAX;
+        assert!(matches!(
+            Aggregate::to_record_batch(&huge),
+            Err(Error::DecimalOverflow(v)) if v == Decimal::MAX
+        ));
+
+        let dir = std::env::temp_dir().join(format!("polygon-columnar-{}", std::process::id()));
+        let paths = write_partitioned(&dir, "AAPL", &rows).unwrap();
+        // The last bar starts after midnight US/Eastern
+        assert_eq!(paths.len(), 2);
+        assert!(paths[0].ends_with("ticker=AAPL/date=2021-03-01/data.parquet"));
+        let crypto = write_partitioned(&dir, "X:BTCUSD", &rows[..1]).unwrap();
+        assert!(crypto[0].ends_with("ticker=X_BTCUSD/date=2021-03-01/data.parquet"));
+
+        let file = File::open(&paths[0]).unwrap();
+        let batches: Vec<RecordBatch> = ParquetRecordBatchReaderBuilder::try_new(file)
+            .unwrap()
+            .build()
+            .unwrap()
+            .collect::<std::result::Result<_, _>>()
+            .unwrap();
+        assert_eq!(batches[0].num_rows(), 2);
+        assert_eq!(
+            batches[0].schema().field(0).data_type(),
+            &DataType::Timestamp(TimeUnit::Nanosecond, Some("UTC".into()))
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
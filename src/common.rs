@@ -0,0 +1,569 @@
+use crate::errors::{Error, Result};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_repr::*;
+use std::borrow::Borrow;
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+use std::sync::Arc;
+
+#[derive(Serialize_repr, Deserialize_repr, Debug, Clone, PartialEq)]
+#[repr(u8)]
+pub enum Tape {
+    A = 1,
+    B = 2,
+    C = 3,
+}
+
+#[derive(Serialize_repr, Deserialize_repr, Debug, Clone, PartialEq, Hash, Eq, Copy)]
+#[repr(u8)]
+pub enum TradeCondition {
+    /// A trade made without stated conditions is deemed regular way for settlement on the third
+    /// business day following the transaction date.
+    RegularSale = 0,
+    /// A transaction made on the Exchange as a result of an Exchange acquisition.
+    Acquisition = 1,
+    /// A trade where the price reported is based upon an average of the prices for transactions in
+    /// a security during all or any portion of the trading day.
+    AveragePriceTrade = 2,
+    /// A sale condition code that identifies a NYSE trade that has been automatically executed
+    /// without the potential benefit of price improvement.
+    AutomaticExecution = 3,
+    /// The combining of multiple odd-lot or round-lot orders for the same security so that they
+    /// can all be executed at the same time. All affected clients must agree to the bunching
+    /// before the order is submitted. Bunched trades may also be referred to as block trades.
+    BunchedTrade = 4,
+    /// A bunched trade that is reported late.
+    BunchedSoldTrade = 5,
+    CapElection = 6,
+    /// A transaction which requires delivery of securities and payment on the same day the trade
+    /// takes place.
+    CashSale = 7,
+    /// The Participant Closing Price represents the last qualifying trade paid for a security by a
+    /// Participant during the trading day.
+    ClosingPrints = 8,
+    /// Indicates that the trade resulted from a Market Center’s crossing session.
+    CrossTrade = 9,
+    /// The transaction that constituted the trade-through was the execution of an order at a price
+    /// that was not based, directly or indirectly, on the quoted price of the security at the time
+    /// of execution, and for which the material terms were not reasonably determinable at the time
+    /// the commitment to execute the order was made (REG NMS 611b7).
+    DerivativelyPriced = 10,
+    /// Distribution stock refers to a large blocks of a security that are carefully sold into the
+    /// market gradually in smaller blocks so as to inundate the market with sell orders for the
+    /// security and driving down its price.
+    Distribution = 11,
+    /// Identifies a trade that was executed outside of regular primary market hours and is
+    /// reported as an extended hours trade.
+    FormT = 12,
+    /// Identifies a trade that takes place outside of regular market hours and is reported as an
+    /// extended hours trade out of sequence and at a time different from the actual transaction
+    /// time.
+    ExtendedTradingHoursSoldOutOfSequence = 13,
+    /// The transaction that constituted the trade-through was the execution of an order identified
+    /// as an Intermarket Sweep Order.
+    IntermarketSweep = 14,
+    /// Indicates the ‘Official’ closing value as determined by a Market Center. This transaction
+    /// report will contain the market center generated closing price.
+    MarketCenterOfficialClose = 15,
+    /// Indicates the ‘Official’ opening value as determined by a Market Center. This transaction
+    /// report will contain the market center generated opening price.
+    MarketCenterOfficialOpen = 16,
+    /// The trade that constituted the trade-through was a single priced opening transaction by the
+    /// Market Center (REG NMS Rule 611b3).
+    MarketCenterOpeningTrade = 17,
+    /// The trade that constituted the trade-through was a single priced reopening transaction by
+    /// the Market Center (REG NMS Rule 611b3).
+    MarketCenterReopeningTrade = 18,
+    /// The transaction that constituted the trade-through was a single priced closing transaction
+    /// by the Market Center (REG NMS Rule 611b3).
+    MarketCenterClosingTrade = 19,
+    /// A transaction that requires the delivery of securities on the first business day following
+    /// the trade date.
+    NextDay = 20,
+    /// Indicates a regular market session trade transaction that carries a price that is
+    /// significantly away from the prevailing consolidated or primary market value at the time of
+    /// the transaction.
+    PriceVariationTrade = 21,
+    /// A sale condition that identifies a trade based on a price at a prior point in time, i.e.,
+    /// more than 90 seconds prior to the time of the trade report. The execution time of the trade
+    /// will be the time of the prior reference price.
+    PriorReferencePrice = 22,
+    /// A Seller’s Option transaction gives the seller the right to deliver the security at any
+    /// time within a specific period, ranging from not less than two calendar days, to not more
+    /// than sixty calendar days. A security offered “Seller’s Option” may command a lesser price
+    /// than if offered “Regular Way”.
+    Rule155Trade = 23,
+    /// "To qualify as a NYSE Rule 127 the trade is executed outside the present quote and meets
+    /// one or both of the following conditions: 1. has a volume of 10,000 shares or more and/or 2.
+    /// has a dollar value of $200,000 or more."
+    Rule127Trade = 24,
+    /// The trading day's first drawings of a symbol's candlestick charts.
+    OpeningPrints = 25,
+    Opened = 26,
+    /// A Seller’s Option transaction gives the seller the right to deliver the security at any
+    /// time within a specific period, ranging from not less than two calendar days, to not more
+    /// than sixty calendar days. A security offered “Seller’s Option” may command a lesser price
+    /// than if offered “Regular Way”.
+    StoppedStockRegularTrade = 27,
+    /// The transaction or group of transactions reported as a result of a single- priced
+    /// re-opening event by the Market Center.
+    ReopeningPrints = 28,
+    /// A Seller’s Option transaction gives the seller the right to deliver the security at any
+    /// time within a specific period, ranging from not less than two calendar days, to not more
+    /// than sixty calendar days. A security offered “Seller’s Option” may command a lesser price
+    /// than if offered “Regular Way”.
+    Seller = 29,
+    /// Sold Last sale condition modifier is used when a trade prints in sequence but is reported
+    /// late OR the trade is printed by Amex in conformance to the One or Two Point Rule. A Sold
+    /// Last transaction should only impact the consolidated last sale price for an issue if the
+    /// market center reporting the sold last transaction also reported the transaction setting the
+    /// current last sale price.
+    SoldLast = 30,
+    SoldOut = 32,
+    /// Sold Out of Sequence is used when a trade is printed (reported) out of sequence and at a
+    /// time different from the actual transaction time.
+    SoldOutOfSequence = 33,
+    /// An execution in two markets when the specialist or Market Maker in the market first
+    /// receiving the order agrees to execute a portion of it at whatever price is realized in
+    /// another market to which the balance of the order is forwarded for execution.
+    SplitTrade = 34,
+    /// This is typically the stock portion of a delta neutral option trade executed by an option
+    /// market maker.
+    StockOption = 35,
+    /// Market Centers will have the ability to identify regular trades being reported during
+    /// specific events as out of the ordinary by appending a new sale condition code Yellow Flag
+    /// (“Y”) on each transaction reported to the UTP SIP. The new sale condition “.Y” will be
+    /// eligible to update all market center and consolidated statistics. In certain instances, the
+    /// UTP SIP will be required to append the .Y for the market center for trades reported as
+    /// regular-way (Sale Condition @)
+    YellowFlagRegularTrade = 36,
+    /// The Odd Lot Trade modifier will distinguish a trade resulting from a market center's
+    /// execution in increments less than the defined round lot size.
+    OddLotTrade = 37,
+    /// A transaction executed by the Listing Market to establish the official Consolidated Last
+    /// Price as indicated by the Listing Exchange.
+    CorrectedConsolidatedClose = 38,
+    Unknown = 39,
+    /// Trades received from a non-primary Participant during a primary market regulatory halt.
+    /// These trades are held by the CTS Processor and are disseminated after the close of the
+    /// primary market with an appropriate Held Trade Indicator code applicable to the trade.
+    Held = 40,
+    /// The Trade Through rule is a 20 year-old rule applied to NYSE-listed stocks that states that
+    /// when a market receives an order, it cannot execute it at a price inferior to any found on
+    /// another market. In modern electronic markets where trades are executed in milliseconds,
+    /// this rule can prevent a broker’s ability to meet their “best execution” obligation--because
+    /// speed provides certainty that the price that is advertised can be accessed.:w
+    ///
+    TradeThruExempt = 41,
+    NonEligible = 42,
+    NonEligibleExtended = 43,
+    Cancelled = 44,
+    Recovery = 45,
+    /// Denotes a correction to the last indication or new indication. It will contain the
+    /// corrected approximation of what that security's opening or reopening price range (Bid and
+    /// Offer prices, no sizes) will be when trading resumes after a delayed opening or after a
+    /// trading halt.
+    Correction = 46,
+    AsOf = 47,
+    AsOfCorrection = 48,
+    AsOfCancel = 49,
+    Oob = 50,
+    Summary = 51,
+    /// A Sale Condition code used to identify a transaction where the execution of the transaction
+    /// is contingent upon some event.
+    ContingentTrade = 52,
+    /// A transaction consisting of two or more component orders executed as agent or principal
+    /// where the execution of one component is contingent upon the execution of all other
+    /// components at or near the same time and the price is determined by the relationship between
+    /// the component orders and not the current market price for the security.
+    QualifiedContingentTrade = 53,
+    Errored = 54,
+    OpeningReopeningTradeDetail = 55,
+    IntradayTradeDetail = 56,
+    ShortSaleRestrictionsActivated = 57,
+    ShortSaleRestrictionsContinued = 58,
+    ShortSaleRestrictionsDeactivated = 59,
+    /// Any stock that has dropped more than 10% intraday has SSR in effect for that day and the following.
+    ShortSaleRestrictionsInEffect = 60,
+    FinancialStatusNormal = 61,
+    FinancialStatusBankrupt = 62,
+    FinancialStatusDeficient = 63,
+    FinancialStatusDelinquent = 64,
+    FinancialStatusBankruptAndDeficient = 65,
+    FinancialStatusBankruptAndDelinquent = 66,
+    FinancialStatusDeficientAndDelinquent = 67,
+    FinancialStatusDeficientDelinquentAndBankrupt = 68,
+    FinancialStatusLiquidation = 69,
+    FinancialStatusCreationsSuspended = 70,
+    FinancialStatusRedemptionsSuspended = 71,
+    FinancialStatusCreationsAndOrRedemptionsSuspended = 72,
+}
+
+#[derive(Serialize_repr, Deserialize_repr, Debug, Clone, PartialEq, Hash, Eq)]
+#[repr(u8)]
+pub enum QuoteCondition {
+    Regular = 0,
+    RegularTwoSidedOpen = 1,
+    RegularOneSidedOpen = 2,
+    SlowAsk = 3,
+    SlowBid = 4,
+    SlowBidASk = 5,
+    SlowDueLrpBid = 6,
+    SlowDueLrpAsk = 7,
+    SlowDueNyseLrp = 8,
+    SlowDueSetSlowListBidAsk = 9,
+    ManualAskAutomatedBid = 10,
+    ManualBidAutomatedAsk = 11,
+    ManualBidAndAsk = 12,
+    Opening = 13,
+    Closing = 14,
+    Closed = 15,
+    Resume = 16,
+    FastTrading = 17,
+    TradingRangeIndication = 18,
+    MarketMakerQuotesClosed = 19,
+    NonFirm = 20,
+    NewsDissemination = 21,
+    OrderInflux = 22,
+    OrderImbalance = 23,
+    DueToRelatedSecurityNewsDissemination = 24,
+    DueToRelatedSecurityNewsPending = 25,
+    AdditionalInformation = 26,
+    NewsPending = 27,
+    AdditionalInformationDueToRelatedSecurity = 28,
+    DueToRelatedSecurity = 29,
+    InViewOfCommon = 30,
+    EquipmentChangeover = 31,
+    NoOpenNoResponse = 32,
+    SubPennyTrading = 33,
+    AutomatedBidNoOfferNoBid = 34,
+    LuldPriceBand = 35,
+    MarketWideCircuitBreakerLevel1 = 36,
+    MarketWideCircuitBreakerLevel2 = 37,
+    MarketWideCircuitBreakerLevel3 = 38,
+    RepublishedLuldPriceBand = 39,
+    OnDemandAuction = 40,
+    CashOnlySettlement = 41,
+    NextDaySettlement = 42,
+    LuldTradingPause = 43,
+    SlowDuelRpBidAsk = 71,
+    Cancel = 80,
+    CorrectedPrice = 81,
+    SipGenerated = 82,
+    Unknown = 83,
+    CrossedMarket = 84,
+    LockedMarket = 85,
+    DepthOnOfferSide = 86,
+    DepthOnBidSide = 87,
+    DepthOnBidAndOffer = 88,
+    PreOpeningIndication = 89,
+    SyndicateBid = 90,
+    PreSyndicateBid = 91,
+    PenaltyBid = 92,
+}
+
+/// An asset class, which is also given by the prefix of a [`Ticker`].
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetClass {
+    Stocks,
+    /// Option contracts, prefixed with `O:`, e.g. `O:SPY251219C00650000`.
+    Options,
+    /// Crypto pairs, prefixed with `X:`, e.g. `X:BTCUSD`.
+    Crypto,
+    /// Currency pairs, prefixed with `C:`, e.g. `C:EURUSD`.
+    Fx,
+    /// Indices, prefixed with `I:`, e.g. `I:SPX`.
+    Indices,
+}
+
+impl AssetClass {
+    fn from_prefix(prefix: &str) -> Option<Self> {
+        match prefix {
+            "O" => Some(AssetClass::Options),
+            "X" => Some(AssetClass::Crypto),
+            "C" => Some(AssetClass::Fx),
+            "I" => Some(AssetClass::Indices),
+            _ => None,
+        }
+    }
+}
+
+/// A ticker symbol, such as `AAPL`, `BRK.B` or `X:BTCUSD`.
+///
+/// Tickers are normalized to Polygon's format: share classes are separated by a dot, the
+/// separators in currency pairs and the padding in option symbols are removed, and symbols are
+/// uppercased unless they mix cases, like the preferred share `BACpK`. So `brk-b`, `x:btc-usd` and
+/// `O:SPY   251219C00650000` become `BRK.B`, `X:BTCUSD` and `O:SPY251219C00650000`. Parsing a
+/// ticker also validates it, while converting from a string only normalizes it.
+///
+/// Clones share the same allocation, so tickers are cheap to use as map keys. A map keyed by
+/// tickers can be looked up with a `&str`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Ticker(Arc<str>);
+
+impl Ticker {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    fn split_prefix(&self) -> (Option<AssetClass>, &str) {
+        match self.0.split_once(':') {
+            Some((prefix, symbol)) => match AssetClass::from_prefix(prefix) {
+                Some(class) => (Some(class), symbol),
+                None => (None, &self.0),
+            },
+            None => (None, &self.0),
+        }
+    }
+
+    /// The asset class of the ticker. Tickers without a known prefix are stocks.
+    pub fn asset_class(&self) -> AssetClass {
+        self.split_prefix().0.unwrap_or(AssetClass::Stocks)
+    }
+
+    /// The ticker without its asset class prefix, e.g. `BTCUSD` for `X:BTCUSD`.
+    pub fn symbol(&self) -> &str {
+        self.split_prefix().1
+    }
+
+    /// The ticker as a file or directory name, e.g. `X_BTCUSD` for `X:BTCUSD`, since Windows
+    /// doesn't allow `:` in paths.
+    pub fn file_name(&self) -> String {
+        self.0.replace(':', "_")
+    }
+
+    /// The share class of a stock, e.g. `B` for `BRK.B`.
+    pub fn share_class(&self) -> Option<&str> {
+        match self.split_prefix() {
+            (None, symbol) => symbol.split_once('.').map(|(_, class)| class),
+            _ => None,
+        }
+    }
+
+    fn validate(&self) -> std::result::Result<(), &'static str> {
+        let alphanumeric = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric());
+        let digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+        if self.0.is_empty() {
+            return Err("empty ticker");
+        }
+        match self.split_prefix() {
+            (None, _) if self.0.contains(':') => Err("unknown asset class prefix"),
+            (None, symbol) => {
+                if symbol.split('.').count() <= 2 && symbol.split('.').all(alphanumeric) {
+                    Ok(())
+                } else {
+                    Err("expected a root symbol and an optional share class")
+                }
+            }
+            (Some(AssetClass::Options), symbol) => {
+                // OCC symbology: root, expiration date, put or call, and strike price
+                let valid = symbol.len() > 15 && symbol.len() <= 21 && symbol.is_ascii() && {
+                    let (root, contract) = symbol.split_at(symbol.len() - 15);
+                    alphanumeric(root)
+                        && digits(&contract[..6])
+                        && matches!(&contract[6..7], "C" | "P")
+                        && digits(&contract[7..])
+                };
+                if valid {
+                    Ok(())
+                } else {
+                    Err("expected an OCC option symbol")
+                }
+            }
+            (Some(AssetClass::Crypto | AssetClass::Fx), symbol) if !alphanumeric(symbol) => {
+                Err("expected a currency pair")
+            }
+            (Some(_), symbol) if !alphanumeric(symbol) => Err("expected an alphanumeric symbol"),
+            (Some(_), _) => Ok(()),
+        }
+    }
+}
+
+fn normalize(ticker: &str) -> String {
+    let ticker = ticker.trim();
+    let without = |s: &str, separators: &[char]| s.replace(separators, "");
+    match ticker.split_once(':') {
+        Some((prefix, symbol)) => {
+            let prefix = prefix.to_uppercase();
+            match AssetClass::from_prefix(&prefix) {
+                Some(AssetClass::Crypto | AssetClass::Fx) => {
+                    format!(
+                        "{}:{}",
+                        prefix,
+                        without(symbol, &['-', '/', ' ']).to_uppercase()
+                    )
+                }
+                Some(_) => format!("{}:{}", prefix, without(symbol, &[' ']).to_uppercase()),
+                None => ticker.to_uppercase(),
+            }
+        }
+        None => {
+            let symbol = ticker.replace(&['-', '/', ' '][..], ".");
+            // Polygon marks preferred shares with a lowercase `p`, e.g. `BACpK`, so only symbols
+            // written entirely in lowercase are uppercased
+            if symbol.chars().any(|c| c.is_ascii_uppercase()) {
+                symbol
+            } else {
+                symbol.to_uppercase()
+            }
+        }
+    }
+}
+
+impl FromStr for Ticker {
+    type Err = Error;
+
+    /// Parse and validate a ticker.
+    fn from_str(s: &str) -> Result<Self> {
+        let ticker = Ticker::from(s);
+        ticker.validate().map_err(|reason| Error::InvalidTicker {
+            ticker: s.to_string(),
+            reason,
+        })?;
+        Ok(ticker)
+    }
+}
+
+impl From<&str> for Ticker {
+    fn from(s: &str) -> Self {
+        Ticker(normalize(s).into())
+    }
+}
+
+impl From<String> for Ticker {
+    fn from(s: String) -> Self {
+        Ticker::from(s.as_str())
+    }
+}
+
+impl From<&String> for Ticker {
+    fn from(s: &String) -> Self {
+        Ticker::from(s.as_str())
+    }
+}
+
+impl From<&Ticker> for Ticker {
+    fn from(ticker: &Ticker) -> Self {
+        ticker.clone()
+    }
+}
+
+impl From<Ticker> for String {
+    fn from(ticker: Ticker) -> Self {
+        ticker.0.to_string()
+    }
+}
+
+impl fmt::Display for Ticker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Deref for Ticker {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Ticker {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Ticker {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq<str> for Ticker {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Ticker {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl PartialEq<String> for Ticker {
+    fn eq(&self, other: &String) -> bool {
+        *self.0 == **other
+    }
+}
+
+impl Serialize for Ticker {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+/// Tickers are deserialized as they are, since they usually come from Polygon.
+impl<'de> Deserialize<'de> for Ticker {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        String::deserialize(deserializer).map(|s| Ticker(s.into()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn normalize_tickers() {
+        let parse = |s: &str| s.parse::<Ticker>().unwrap();
+        assert_eq!(parse(" brk-b "), "BRK.B");
+        assert_eq!(parse("BRK/B").share_class(), Some("B"));
+        assert_eq!(parse("BACpK"), "BACpK");
+        assert_eq!(parse("x:btc-usd"), "X:BTCUSD");
+        assert_eq!(parse("C:EUR/USD").asset_class(), AssetClass::Fx);
+        assert_eq!(parse("I:SPX").symbol(), "SPX");
+        let option = parse("O:SPY   251219C00650000");
+        assert_eq!(option, "O:SPY251219C00650000");
+        assert_eq!(option.asset_class(), AssetClass::Options);
+        assert_eq!(option.share_class(), None);
+        assert_eq!(parse("AAPL").asset_class(), AssetClass::Stocks);
+    }
+
+    #[test]
+    fn reject_invalid_tickers() {
+        for invalid in [
+            "",
+            "Z:ABC",
+            "BRK.B.C",
+            "AA$",
+            "O:SPY2512C00650000",
+            "X:BTC.USD",
+        ] {
+            assert!(
+                matches!(invalid.parse::<Ticker>(), Err(Error::InvalidTicker { .. })),
+                "{:?}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn ticker_keys() {
+        let ticker = Ticker::from("msft");
+        let mut prices = HashMap::new();
+        prices.insert(ticker.clone(), 1);
+        assert_eq!(prices.get("MSFT"), Some(&1));
+        assert_eq!(Ticker::from("X:BTCUSD").file_name(), "X_BTCUSD");
+        assert!(Arc::ptr_eq(&ticker.0, &ticker.clone().0));
+
+        let json = serde_json::to_string(&ticker).unwrap();
+        assert_eq!(json, r#""MSFT""#);
+        let from_server: Ticker = serde_json::from_str(r#""odd ticker""#).unwrap();
+        assert_eq!(from_server, "odd ticker");
+    }
+}
@@ -0,0 +1,286 @@
+//! Client configuration loaded from environment variables and an optional TOML file.
+//!
+//! Values are read from the defaults, then from the TOML file, then from the environment, with
+//! later sources taking precedence:
+//!
+//! | Environment variable            | TOML key               | Default                  |
+//! |---------------------------------|------------------------|--------------------------|
+//! | `POLYGON_TOKEN`                 | `api_key`              | required                 |
+//! | `POLYGON_BASE_URL`              | `base_url`             | `https://api.polygon.io` |
+//! | `POLYGON_WS_CLUSTER`            | `ws_cluster`           | `stocks`                 |
+//! | `POLYGON_WS_FEED`               | `ws_feed`              | `realtime`               |
+//! | `POLYGON_TIMEOUT_SECS`          | `timeout_secs`         | none                     |
+//! | `POLYGON_CONNECT_TIMEOUT_SECS`  | `connect_timeout_secs` | none                     |
+//! | `POLYGON_REQUESTS_PER_MINUTE`   | `requests_per_minute`  | none                     |
+//!
+//! The TOML file is read from the path in `POLYGON_CONFIG` when using [`Config::from_env`].
+
+use crate::errors::{Error, Result};
+use serde::Deserialize;
+use std::convert::TryFrom;
+use std::env::VarError;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Which WebSocket feed to connect to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Feed {
+    RealTime,
+    /// Data delayed by 15 minutes, available on cheaper plans.
+    Delayed,
+}
+
+impl FromStr for Feed {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<Self, ()> {
+        match s.to_lowercase().as_str() {
+            "realtime" => Ok(Feed::RealTime),
+            "delayed" => Ok(Feed::Delayed),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileConfig {
+    api_key: Option<String>,
+    base_url: Option<String>,
+    ws_cluster: Option<String>,
+    ws_feed: Option<Feed>,
+    timeout_secs: Option<u64>,
+    connect_timeout_secs: Option<u64>,
+    requests_per_minute: Option<u32>,
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct Config {
+    pub api_key: String,
+    pub base_url: String,
+    /// The asset class to stream, e.g. `stocks`, `options` or `crypto`.
+    pub ws_cluster: String,
+    pub ws_feed: Feed,
+    pub timeout: Option<Duration>,
+    pub connect_timeout: Option<Duration>,
+    pub requests_per_minute: Option<u32>,
+}
+
+impl fmt::Debug for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Config")
+            .field("api_key", &"<redacted>")
+            .field("base_url", &self.base_url)
+            .field("ws_cluster", &self.ws_cluster)
+            .field("ws_feed", &self.ws_feed)
+            .field("timeout", &self.timeout)
+            .field("connect_timeout", &self.connect_timeout)
+            .field("requests_per_minute", &self.requests_per_minute)
+            .finish()
+    }
+}
+
+impl Config {
+    pub fn new<T: ToString>(api_key: T) -> Self {
+        Self {
+            api_key: api_key.to_string(),
+            base_url: "https://api.polygon.io".into(),
+            ws_cluster: "stocks".into(),
+            ws_feed: Feed::RealTime,
+            timeout: None,
+            connect_timeout: None,
+            requests_per_minute: None,
+        }
+    }
+
+    /// Load the config from the environment, and from the TOML file in `POLYGON_CONFIG` if it is
+    /// set.
+    pub fn from_env() -> Result<Self> {
+        let file = match std::env::var("POLYGON_CONFIG") {
+            Ok(path) => Some(read_file(Path::new(&path))?),
+            Err(_) => None,
+        };
+        Self::load(file.unwrap_or_default(), |name| std::env::var(name))
+    }
+
+    /// Load the config from a TOML file, overridden by any environment variables that are set.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::load(read_file(path.as_ref())?, |name| std::env::var(name))
+    }
+
+    fn load<F>(file: FileConfig, var: F) -> Result<Self>
+    where
+        F: Fn(&str) -> std::result::Result<String, VarError>,
+    {
+        let parse = |name: &str| -> Result<Option<String>> {
+            match var(name) {
+                Ok(value) => Ok(Some(value)),
+                Err(VarError::NotPresent) => Ok(None),
+                Err(source) => Err(Error::MissingEnv {
+                    source,
+                    variable: name.to_string(),
+                }),
+            }
+        };
+        let parsed = |name: &str| -> Result<Option<u64>> {
+            parse(name)?
+                .map(|value| {
+                    value.parse().map_err(|_| Error::InvalidEnv {
+                        variable: name.to_string(),
+                        value,
+                    })
+                })
+                .transpose()
+        };
+
+        let api_key = match (parse("POLYGON_TOKEN")?, file.api_key) {
+            (Some(key), _) | (None, Some(key)) => key,
+            (None, None) => {
+                return Err(Error::MissingEnv {
+                    source: VarError::NotPresent,
+                    variable: "POLYGON_TOKEN".into(),
+                })
+            }
+        };
+        let mut config = Self::new(api_key);
+        if let Some(base_url) = parse("POLYGON_BASE_URL")?.or(file.base_url) {
+            config.base_url = base_url.trim_end_matches('/').to_string();
+        }
+        if let Some(cluster) = parse("POLYGON_WS_CLUSTER")?.or(file.ws_cluster) {
+            config.ws_cluster = cluster;
+        }
+        let feed = match parse("POLYGON_WS_FEED")? {
+            Some(value) => Some(value.parse().map_err(|_| Error::InvalidEnv {
+                variable: "POLYGON_WS_FEED".into(),
+                value,
+            })?),
+            None => file.ws_feed,
+        };
+        if let Some(feed) = feed {
+            config.ws_feed = feed;
+        }
+        config.timeout = parsed("POLYGON_TIMEOUT_SECS")?
+            .or(file.timeout_secs)
+            .map(Duration::from_secs);
+        config.connect_timeout = parsed("POLYGON_CONNECT_TIMEOUT_SECS")?
+            .or(file.connect_timeout_secs)
+            .map(Duration::from_secs);
+        config.requests_per_minute = match parsed("POLYGON_REQUESTS_PER_MINUTE")? {
+            Some(n) => Some(u32::try_from(n).map_err(|_| Error::InvalidEnv {
+                variable: "POLYGON_REQUESTS_PER_MINUTE".into(),
+                value: n.to_string(),
+            })?),
+            None => file.requests_per_minute,
+        };
+        Ok(config)
+    }
+
+    /// The URL of the configured WebSocket feed and cluster.
+    pub fn ws_url(&self) -> String {
+        let host = match self.ws_feed {
+            Feed::RealTime => "socket",
+            Feed::Delayed => "delayed",
+        };
+        format!("wss://{}.polygon.io/{}", host, self.ws_cluster)
+    }
+
+    /// A REST client for the configured base URL, with the configured timeouts and rate limit.
+    /// Fails if the rate limit is zero requests per minute.
+    #[cfg(feature = "rest")]
+    pub fn throttled_client(&self) -> Result<crate::rest::ThrottledClient> {
+        let mut builder =
+            crate::rest::ThrottledClient::builder(&self.api_key).base_url(&self.base_url);
+        if let Some(requests) = self.requests_per_minute {
+            builder = builder.requests_per_minute(requests);
+        }
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        builder.build()
+    }
+
+    /// A blocking REST client for the configured base URL and timeout.
+    #[cfg(feature = "blocking")]
+    pub fn blocking_client(&self) -> crate::rest::blocking::Client {
+        let mut builder =
+            crate::rest::blocking::Client::builder(&self.api_key).base_url(&self.base_url);
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        builder.build()
+    }
+
+    /// A connection to the configured WebSocket feed, which subscribes to `events` for `assets`.
+    #[cfg(feature = "ws")]
+    pub fn connection<E: ToString, A: ToString>(
+        &self,
+        events: &[E],
+        assets: &[A],
+    ) -> crate::ws::Connection {
+        let connection = crate::ws::Connection::new(self.ws_url(), &self.api_key, events, assets);
+        match self.connect_timeout {
+            Some(timeout) => connection.connect_timeout(timeout),
+            None => connection,
+        }
+    }
+}
+
+fn read_file(path: &Path) -> Result<FileConfig> {
+    let contents = std::fs::read_to_string(path)?;
+    Ok(toml::from_str(&contents)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn load(file: &str, vars: &[(&str, &str)]) -> Result<Config> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        Config::load(toml::from_str(file)?, |name| {
+            vars.get(name).cloned().ok_or(VarError::NotPresent)
+        })
+    }
+
+    #[test]
+    fn env_overrides_file() {
+        let file = r#"
+            api_key = "FILE"
+            ws_feed = "delayed"
+            timeout_secs = 10
+            requests_per_minute = 5
+        "#;
+        let config = load(
+            file,
+            &[("POLYGON_TOKEN", "ENV"), ("POLYGON_TIMEOUT_SECS", "30")],
+        )
+        .unwrap();
+        assert_eq!(config.api_key, "ENV");
+        assert_eq!(config.timeout, Some(Duration::from_secs(30)));
+        assert_eq!(config.requests_per_minute, Some(5));
+        assert_eq!(config.ws_url(), "wss://delayed.polygon.io/stocks");
+        assert!(!format!("{:?}", config).contains("ENV"));
+    }
+
+    #[test]
+    fn missing_and_invalid_values() {
+        let res = load("", &[]);
+        assert!(
+            matches!(res, Err(Error::MissingEnv { variable, .. }) if variable == "POLYGON_TOKEN")
+        );
+        let res = load("", &[("POLYGON_TOKEN", "T"), ("POLYGON_WS_FEED", "fast")]);
+        assert!(
+            matches!(res, Err(Error::InvalidEnv { variable, .. }) if variable == "POLYGON_WS_FEED")
+        );
+        assert!(matches!(load("token = 1", &[]), Err(Error::Toml(_))));
+    }
+}
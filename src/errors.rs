@@ -1,13 +1,17 @@
 #[cfg(feature = "ws")]
 use crate::ws::PolygonAction;
+#[cfg(feature = "rest")]
+use serde::Deserialize;
 use thiserror::Error;
+#[cfg(feature = "rest")]
+use vila::StatusCode;
 
 #[cfg(feature = "ws")]
 use tokio_tungstenite::tungstenite;
 
 #[derive(Debug, Error)]
 pub enum Error {
-    #[cfg(feature = "rest")]
+    #[cfg(any(feature = "rest", feature = "ws"))]
     #[error("Missing environment variable: {variable}")]
     MissingEnv {
         #[source]
@@ -15,19 +19,131 @@ pub enum Error {
         variable: String,
     },
 
+    #[cfg(any(feature = "rest", feature = "ws"))]
+    #[error("Invalid value for {variable}: {value:?}")]
+    InvalidEnv { variable: String, value: String },
+
+    #[cfg(any(feature = "rest", feature = "ws"))]
+    #[error("Invalid config file: {0}")]
+    Toml(#[from] toml::de::Error),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// A ticker that doesn't match the format of its asset class.
+    #[error("Invalid ticker {ticker:?}: {reason}")]
+    InvalidTicker {
+        ticker: String,
+        reason: &'static str,
+    },
+
+    #[cfg(feature = "columnar")]
+    #[error("Arrow error: {0}")]
+    Arrow(#[from] arrow::error::ArrowError),
+
+    #[cfg(feature = "columnar")]
+    #[error("Parquet error: {0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
+
+    /// A decimal too large to be stored as `decimal128(38, 10)`.
+    #[cfg(feature = "columnar")]
+    #[error("Decimal {0} cannot be stored with a scale of 10")]
+    DecimalOverflow(rust_decimal::Decimal),
+
+    #[cfg(feature = "export")]
+    #[error("CSV error: {0}")]
+    Csv(#[from] csv::Error),
+
+    #[cfg(feature = "export")]
+    #[error("Invalid value for {column}: {value:?}")]
+    InvalidField { column: &'static str, value: String },
+
     #[error("Serde error: {error}\nMsg: {msg}")]
     Serde {
         error: serde_json::Error,
         msg: String,
     },
 
+    /// The request could not be sent or its response could not be read, e.g. because the
+    /// connection failed or timed out.
     #[cfg(feature = "rest")]
     #[error(transparent)]
-    Vila(vila::Error),
+    Http(HttpError),
+
+    /// A client was built with invalid settings, e.g. a rate limit of zero requests.
+    #[cfg(feature = "rest")]
+    #[error("Invalid client settings: {0}")]
+    InvalidClient(&'static str),
+
+    /// The request could not be formatted, e.g. because the base URL is invalid.
+    #[cfg(feature = "rest")]
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+
+    /// The API key is missing or invalid.
+    #[cfg(feature = "rest")]
+    #[error("Unauthorized: {message}")]
+    Unauthorized {
+        request_id: Option<String>,
+        message: String,
+    },
+
+    /// The API key is valid, but the plan does not include the requested data.
+    #[cfg(feature = "rest")]
+    #[error("Not entitled: {message}")]
+    NotEntitled {
+        request_id: Option<String>,
+        message: String,
+    },
+
+    #[cfg(feature = "rest")]
+    #[error("Rate limited: {message}")]
+    RateLimited {
+        request_id: Option<String>,
+        message: String,
+    },
+
+    #[cfg(feature = "rest")]
+    #[error("Not found: {message}")]
+    NotFound {
+        request_id: Option<String>,
+        message: String,
+    },
+
+    #[cfg(feature = "rest")]
+    #[error("Bad request: {message}")]
+    BadRequest {
+        request_id: Option<String>,
+        message: String,
+    },
+
+    /// A split or dividend that can't be used to adjust prices, e.g. a split ratio of zero.
+    #[cfg(feature = "rest")]
+    #[error("Invalid {action} for {ticker} on {ex_date}: {value}")]
+    InvalidCorporateAction {
+        action: &'static str,
+        ticker: String,
+        ex_date: chrono::NaiveDate,
+        value: String,
+    },
+
+    /// The response is not cached and the cache is in offline mode.
+    #[cfg(feature = "rest")]
+    #[error("No cached response for {url}")]
+    CacheMiss { url: String },
+
+    /// Any other error response from Polygon, with its HTTP status code.
+    #[cfg(feature = "rest")]
+    #[error("Polygon error. Received status {status}. Message: {message}")]
+    Api {
+        status: u16,
+        request_id: Option<String>,
+        message: String,
+    },
 
     #[cfg(feature = "ws")]
     #[error("Tungstenite error: {0}")]
-    Tungstenite(#[from] tungstenite::Error),
+    Tungstenite(Box<tungstenite::Error>),
 
     #[cfg(feature = "ws")]
     #[error("WebSocket stream has been closed")]
@@ -47,3 +163,183 @@ pub enum Error {
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(feature = "ws")]
+impl From<tungstenite::Error> for Error {
+    fn from(e: tungstenite::Error) -> Self {
+        Error::Tungstenite(Box::new(e))
+    }
+}
+
+/// An error from the HTTP client, which is kept opaque so that the client can change.
+#[cfg(feature = "rest")]
+#[derive(Debug, Error)]
+#[error(transparent)]
+pub struct HttpError(vila::Error);
+
+/// The body Polygon sends along with error responses, e.g.
+/// `{"status":"ERROR","request_id":"...","error":"Unknown API Key"}`.
+#[cfg(feature = "rest")]
+#[derive(Deserialize)]
+struct ErrorEnvelope {
+    status: Option<String>,
+    request_id: Option<String>,
+    error: Option<String>,
+    message: Option<String>,
+}
+
+#[cfg(feature = "rest")]
+impl Error {
+    pub(crate) fn transport<E: Into<vila::Error>>(e: E) -> Self {
+        Error::Http(HttpError(e.into()))
+    }
+
+    /// Build an error from the status and body of a failed response.
+    pub(crate) fn from_response(status: StatusCode, body: String) -> Self {
+        let (request_id, message) = match serde_json::from_str::<ErrorEnvelope>(&body) {
+            Ok(envelope) => (
+                envelope.request_id,
+                envelope.error.or(envelope.message).unwrap_or(body),
+            ),
+            Err(_) => (None, body),
+        };
+        match status {
+            StatusCode::UNAUTHORIZED => Error::Unauthorized {
+                request_id,
+                message,
+            },
+            StatusCode::FORBIDDEN => Error::NotEntitled {
+                request_id,
+                message,
+            },
+            StatusCode::TOO_MANY_REQUESTS => Error::RateLimited {
+                request_id,
+                message,
+            },
+            StatusCode::NOT_FOUND => Error::NotFound {
+                request_id,
+                message,
+            },
+            StatusCode::BAD_REQUEST => Error::BadRequest {
+                request_id,
+                message,
+            },
+            status => Error::Api {
+                status: status.as_u16(),
+                request_id,
+                message,
+            },
+        }
+    }
+
+    /// Build an error from a body that could not be parsed as the expected response, which
+    /// Polygon sometimes sends with a successful status.
+    pub(crate) fn from_body(error: serde_json::Error, msg: String) -> Self {
+        let status = match serde_json::from_str::<ErrorEnvelope>(&msg) {
+            Ok(ErrorEnvelope {
+                status: Some(status),
+                ..
+            }) => status,
+            _ => return Error::Serde { error, msg },
+        };
+        match status.as_str() {
+            "NOT_AUTHORIZED" => Error::from_response(StatusCode::FORBIDDEN, msg),
+            "NOT_FOUND" => Error::from_response(StatusCode::NOT_FOUND, msg),
+            "ERROR" => Error::from_response(StatusCode::BAD_REQUEST, msg),
+            _ => Error::Serde { error, msg },
+        }
+    }
+
+    /// Convert an error of the HTTP client into the typed variants when Polygon sent an error
+    /// response, including error bodies sent with a successful status. This is not a `From`
+    /// impl, so that the HTTP client stays out of the public API.
+    pub(crate) fn from_vila(e: vila::Error) -> Self {
+        match e {
+            vila::Error::ClientError(status, body) | vila::Error::ServerError(status, body) => {
+                Error::from_response(status, body)
+            }
+            vila::Error::Serde { error, msg } => Error::from_body(error, msg),
+            e => Error::transport(e),
+        }
+    }
+
+    /// The ID Polygon assigned to the failed request, if the error came from Polygon.
+    pub fn request_id(&self) -> Option<&str> {
+        match self {
+            Error::Unauthorized { request_id, .. }
+            | Error::NotEntitled { request_id, .. }
+            | Error::RateLimited { request_id, .. }
+            | Error::NotFound { request_id, .. }
+            | Error::BadRequest { request_id, .. }
+            | Error::Api { request_id, .. } => request_id.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "rest"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn from_vila_error() {
+        let e = Error::from_vila(vila::Error::ClientError(
+            StatusCode::FORBIDDEN,
+            r#"{"status":"NOT_AUTHORIZED","request_id":"a1b2c3","message":"You are not entitled to this data."}"#.into(),
+        ));
+        assert!(
+            matches!(&e, Error::NotEntitled { message, .. } if message == "You are not entitled to this data.")
+        );
+        assert_eq!(e.request_id(), Some("a1b2c3"));
+
+        let e = Error::from_vila(vila::Error::ServerError(
+            StatusCode::BAD_GATEWAY,
+            "Bad Gateway".into(),
+        ));
+        assert!(matches!(
+            e,
+            Error::Api {
+                status: 502,
+                request_id: None,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn from_vila_serde_error() {
+        let body = r#"{"status":"NOT_AUTHORIZED","request_id":"d4e5","message":"You are not entitled to this data."}"#;
+        let error = serde_json::from_str::<Vec<u8>>(body).unwrap_err();
+        let e = Error::from_vila(vila::Error::Serde {
+            error,
+            msg: body.into(),
+        });
+        assert!(matches!(e, Error::NotEntitled { .. }));
+        assert_eq!(e.request_id(), Some("d4e5"));
+    }
+
+    #[tokio::test]
+    async fn from_vila_client() {
+        use crate::rest::{client_with_url, GetLastTrade};
+        use mockito::{mock, Matcher};
+
+        let _m = mock("GET", "/v2/last/trade/ENTL")
+            .match_query(Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()))
+            .with_status(403)
+            .with_body(r#"{"status":"NOT_AUTHORIZED","request_id":"d4e5","message":"You are not entitled to this data."}"#)
+            .create();
+        let client = client_with_url(&mockito::server_url(), "TOKEN");
+        let e = Error::from_vila(client.send(&GetLastTrade::new("ENTL")).await.unwrap_err());
+        assert!(matches!(e, Error::NotEntitled { .. }));
+        assert_eq!(e.request_id(), Some("d4e5"));
+    }
+
+    #[test]
+    fn from_body() {
+        let body = r#"{"status":"ERROR","request_id":"9e1b","error":"Could not parse the date."}"#;
+        let error = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        let e = Error::from_body(error, body.into());
+        assert!(matches!(e, Error::BadRequest { .. }));
+        assert_eq!(e.request_id(), Some("9e1b"));
+    }
+}
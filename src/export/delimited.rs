@@ -0,0 +1,732 @@
+use super::Page;
+use crate::errors::{Error, Result};
+use chrono::{DateTime, SecondsFormat, TimeZone, Utc};
+use futures::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+use std::io::{Read, Write};
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimestampFormat {
+    /// RFC 3339 in UTC, e.g. `2021-03-01T14:30:00.123456789Z`.
+    Iso8601,
+    EpochMillis,
+    EpochNanos,
+}
+
+/// How lists of conditions are flattened into a single column.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConditionFormat {
+    /// The numeric condition codes joined by a separator, e.g. `12;37`.
+    Joined(char),
+    /// The numeric condition codes as a JSON array, e.g. `[12,37]`.
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CsvOptions {
+    pub timestamps: TimestampFormat,
+    pub conditions: ConditionFormat,
+    pub delimiter: u8,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            timestamps: TimestampFormat::Iso8601,
+            conditions: ConditionFormat::Joined(';'),
+            delimiter: b',',
+        }
+    }
+}
+
+impl CsvOptions {
+    pub fn format_timestamp(&self, t: DateTime<Utc>) -> String {
+        match self.timestamps {
+            TimestampFormat::Iso8601 => t.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            TimestampFormat::EpochMillis => t.timestamp_millis().to_string(),
+            TimestampFormat::EpochNanos => t
+                .timestamp_nanos_opt()
+                .map(|t| t.to_string())
+                .unwrap_or_default(),
+        }
+    }
+
+    fn parse_timestamp(&self, s: &str) -> Option<DateTime<Utc>> {
+        match self.timestamps {
+            TimestampFormat::Iso8601 => DateTime::parse_from_rfc3339(s)
+                .ok()
+                .map(|t| t.with_timezone(&Utc)),
+            TimestampFormat::EpochMillis => Utc.timestamp_millis_opt(s.parse().ok()?).single(),
+            TimestampFormat::EpochNanos => Some(Utc.timestamp_nanos(s.parse().ok()?)),
+        }
+    }
+
+    pub fn format_conditions<C: Serialize>(&self, conditions: &[C]) -> String {
+        match self.conditions {
+            ConditionFormat::Json => serde_json::to_string(conditions).unwrap(),
+            ConditionFormat::Joined(separator) => conditions
+                .iter()
+                .map(|c| serde_json::to_string(c).unwrap())
+                .collect::<Vec<_>>()
+                .join(&separator.to_string()),
+        }
+    }
+
+    fn parse_conditions<C: DeserializeOwned>(&self, s: &str) -> Option<Vec<C>> {
+        match self.conditions {
+            ConditionFormat::Json => serde_json::from_str(s).ok(),
+            ConditionFormat::Joined(_) if s.is_empty() => Some(Vec::new()),
+            ConditionFormat::Joined(separator) => s
+                .split(separator)
+                .map(|c| serde_json::from_str(c).ok())
+                .collect(),
+        }
+    }
+}
+
+/// A row of market data with a fixed set of CSV columns.
+pub trait CsvRecord: Sized {
+    /// The column names, in the order the fields are written.
+    fn header() -> &'static [&'static str];
+
+    fn to_fields(&self, options: &CsvOptions) -> Vec<String>;
+
+    fn from_fields(fields: &mut Fields<'_>) -> Result<Self>;
+}
+
+/// Cursor over the fields of a CSV row, used to parse rows back into records.
+pub struct Fields<'a> {
+    record: &'a csv::StringRecord,
+    header: &'static [&'static str],
+    options: &'a CsvOptions,
+    index: usize,
+}
+
+impl<'a> Fields<'a> {
+    fn next_str(&mut self) -> Result<(&'static str, &'a str)> {
+        let column = self.header.get(self.index).copied().unwrap_or("?");
+        let value = self.record.get(self.index).ok_or(Error::InvalidField {
+            column,
+            value: String::new(),
+        })?;
+        self.index += 1;
+        Ok((column, value))
+    }
+
+    fn invalid(column: &'static str, value: &str) -> Error {
+        Error::InvalidField {
+            column,
+            value: value.to_string(),
+        }
+    }
+
+    pub fn parse<T: FromStr>(&mut self) -> Result<T> {
+        let (column, value) = self.next_str()?;
+        value.parse().map_err(|_| Self::invalid(column, value))
+    }
+
+    /// Parse a field that is empty when the value is missing.
+    pub fn parse_opt<T: FromStr>(&mut self) -> Result<Option<T>> {
+        let (column, value) = self.next_str()?;
+        if value.is_empty() {
+            return Ok(None);
+        }
+        value
+            .parse()
+            .map(Some)
+            .map_err(|_| Self::invalid(column, value))
+    }
+
+    pub fn timestamp(&mut self) -> Result<DateTime<Utc>> {
+        self.timestamp_opt()?.ok_or_else(|| {
+            let column = self.header[self.index - 1];
+            Self::invalid(column, "")
+        })
+    }
+
+    pub fn timestamp_opt(&mut self) -> Result<Option<DateTime<Utc>>> {
+        let (column, value) = self.next_str()?;
+        if value.is_empty() {
+            return Ok(None);
+        }
+        self.options
+            .parse_timestamp(value)
+            .map(Some)
+            .ok_or_else(|| Self::invalid(column, value))
+    }
+
+    /// Parse a field holding a single value in its serde representation, e.g. a condition code.
+    pub fn serde<T: DeserializeOwned>(&mut self) -> Result<T> {
+        let (column, value) = self.next_str()?;
+        serde_json::from_str(value).map_err(|_| Self::invalid(column, value))
+    }
+
+    pub fn conditions<C: DeserializeOwned>(&mut self) -> Result<Vec<C>> {
+        let (column, value) = self.next_str()?;
+        self.options
+            .parse_conditions(value)
+            .ok_or_else(|| Self::invalid(column, value))
+    }
+}
+
+#[cfg(any(feature = "rest", feature = "ws"))]
+fn opt<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+#[cfg(feature = "ws")]
+fn serde_field<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap()
+}
+
+#[cfg(feature = "rest")]
+mod rest_types {
+    use super::*;
+    use crate::rest::{Aggregate, Quote};
+
+    impl CsvRecord for Aggregate {
+        fn header() -> &'static [&'static str] {
+            &[
+                "timestamp",
+                "open",
+                "high",
+                "low",
+                "close",
+                "volume",
+                "vwap",
+                "transactions",
+            ]
+        }
+
+        fn to_fields(&self, options: &CsvOptions) -> Vec<String> {
+            vec![
+                options.format_timestamp(self.t),
+                self.o.to_string(),
+                self.h.to_string(),
+                self.l.to_string(),
+                self.c.to_string(),
+                self.v.to_string(),
+                opt(self.vw),
+                opt(self.n),
+            ]
+        }
+
+        fn from_fields(fields: &mut Fields<'_>) -> Result<Self> {
+            Ok(Self {
+                t: fields.timestamp()?,
+                o: fields.parse()?,
+                h: fields.parse()?,
+                l: fields.parse()?,
+                c: fields.parse()?,
+                v: fields.parse()?,
+                vw: fields.parse_opt()?,
+                n: fields.parse_opt()?,
+            })
+        }
+    }
+
+    impl CsvRecord for Quote {
+        fn header() -> &'static [&'static str] {
+            &[
+                "sip_timestamp",
+                "participant_timestamp",
+                "trf_timestamp",
+                "sequence_number",
+                "conditions",
+                "indicators",
+                "bid_price",
+                "bid_exchange",
+                "bid_size",
+                "ask_price",
+                "ask_exchange",
+                "ask_size",
+                "tape",
+            ]
+        }
+
+        fn to_fields(&self, options: &CsvOptions) -> Vec<String> {
+            vec![
+                options.format_timestamp(self.t),
+                options.format_timestamp(self.y),
+                opt(self.f.map(|f| options.format_timestamp(f))),
+                self.q.to_string(),
+                options.format_conditions(&self.c),
+                opt(self.i.as_ref().map(|i| options.format_conditions(i))),
+                self.bid_price.to_string(),
+                self.bid_exchange.to_string(),
+                self.bid_size.to_string(),
+                self.ask_price.to_string(),
+                self.ask_exchange.to_string(),
+                self.ask_size.to_string(),
+                self.z.to_string(),
+            ]
+        }
+
+        fn from_fields(fields: &mut Fields<'_>) -> Result<Self> {
+            Ok(Self {
+                t: fields.timestamp()?,
+                y: fields.timestamp()?,
+                f: fields.timestamp_opt()?,
+                q: fields.parse()?,
+                c: fields.conditions()?,
+                i: match fields.conditions::<u32>()? {
+                    i if i.is_empty() => None,
+                    i => Some(i),
+                },
+                bid_price: fields.parse()?,
+                bid_exchange: fields.parse()?,
+                bid_size: fields.parse()?,
+                ask_price: fields.parse()?,
+                ask_exchange: fields.parse()?,
+                ask_size: fields.parse()?,
+                z: fields.parse()?,
+            })
+        }
+    }
+}
+
+#[cfg(feature = "ws")]
+mod ws_types {
+    use super::*;
+    use crate::ws::{Aggregate, AskQuote, BidQuote, Quote, Trade};
+
+    impl CsvRecord for Trade {
+        fn header() -> &'static [&'static str] {
+            &[
+                "symbol",
+                "timestamp",
+                "exchange_id",
+                "trade_id",
+                "tape",
+                "price",
+                "size",
+                "conditions",
+            ]
+        }
+
+        fn to_fields(&self, options: &CsvOptions) -> Vec<String> {
+            vec![
+                self.symbol.to_string(),
+                options.format_timestamp(self.timestamp),
+                self.exchange_id.to_string(),
+                self.trade_id.clone(),
+                serde_field(&self.tape),
+                self.price.to_string(),
+                self.size.to_string(),
+                options.format_conditions(&self.conditions),
+            ]
+        }
+
+        fn from_fields(fields: &mut Fields<'_>) -> Result<Self> {
+            Ok(Self {
+                symbol: fields.parse::<String>()?.into(),
+                timestamp: fields.timestamp()?,
+                exchange_id: fields.parse()?,
+                trade_id: fields.parse()?,
+                tape: fields.serde()?,
+                price: fields.parse()?,
+                size: fields.parse()?,
+                conditions: fields.conditions()?,
+            })
+        }
+    }
+
+    /// Quotes only carry the sides that changed, so the columns of a missing side are empty.
+    impl CsvRecord for Quote {
+        fn header() -> &'static [&'static str] {
+            &[
+                "symbol",
+                "timestamp",
+                "bid_exchange",
+                "bid_price",
+                "bid_size",
+                "ask_exchange",
+                "ask_price",
+                "ask_size",
+                "condition",
+            ]
+        }
+
+        fn to_fields(&self, options: &CsvOptions) -> Vec<String> {
+            let bid = self.bid_quote.as_ref();
+            let ask = self.ask_quote.as_ref();
+            vec![
+                self.symbol.to_string(),
+                options.format_timestamp(self.timestamp),
+                opt(bid.map(|q| q.exchange_id)),
+                opt(bid.map(|q| q.price)),
+                opt(bid.map(|q| q.size)),
+                opt(ask.map(|q| q.exchange_id)),
+                opt(ask.map(|q| q.price)),
+                opt(ask.map(|q| q.size)),
+                opt(self.condition.as_ref().map(serde_field)),
+            ]
+        }
+
+        fn from_fields(fields: &mut Fields<'_>) -> Result<Self> {
+            let symbol = fields.parse::<String>()?.into();
+            let timestamp = fields.timestamp()?;
+            let bid = (
+                fields.parse_opt()?,
+                fields.parse_opt()?,
+                fields.parse_opt()?,
+            );
+            let ask = (
+                fields.parse_opt()?,
+                fields.parse_opt()?,
+                fields.parse_opt()?,
+            );
+            let condition = match fields.parse_opt::<u8>()? {
+                Some(c) => {
+                    Some(
+                        serde_json::from_value(c.into()).map_err(|_| Error::InvalidField {
+                            column: "condition",
+                            value: c.to_string(),
+                        })?,
+                    )
+                }
+                None => None,
+            };
+            Ok(Self {
+                symbol,
+                bid_quote: match bid {
+                    (Some(exchange_id), Some(price), Some(size)) => Some(BidQuote {
+                        exchange_id,
+                        price,
+                        size,
+                    }),
+                    _ => None,
+                },
+                ask_quote: match ask {
+                    (Some(exchange_id), Some(price), Some(size)) => Some(AskQuote {
+                        exchange_id,
+                        price,
+                        size,
+                    }),
+                    _ => None,
+                },
+                condition,
+                timestamp,
+            })
+        }
+    }
+
+    impl CsvRecord for Aggregate {
+        fn header() -> &'static [&'static str] {
+            &[
+                "symbol",
+                "start_timestamp",
+                "end_timestamp",
+                "open",
+                "high",
+                "low",
+                "close",
+                "volume",
+                "accumulated_volume",
+                "vwap",
+                "average",
+                "day_open",
+                "average_trade_size",
+            ]
+        }
+
+        fn to_fields(&self, options: &CsvOptions) -> Vec<String> {
+            vec![
+                self.symbol.to_string(),
+                options.format_timestamp(self.start_timestamp),
+                options.format_timestamp(self.end_timestamp),
+                self.open.to_string(),
+                self.high.to_string(),
+                self.low.to_string(),
+                self.close.to_string(),
+                self.volume.to_string(),
+                self.accumulated_volume.to_string(),
+                self.vwap.to_string(),
+                self.average.to_string(),
+                opt(self.day_open),
+                opt(self.average_trade_size),
+            ]
+        }
+
+        fn from_fields(fields: &mut Fields<'_>) -> Result<Self> {
+            Ok(Self {
+                symbol: fields.parse::<String>()?.into(),
+                start_timestamp: fields.timestamp()?,
+                end_timestamp: fields.timestamp()?,
+                open: fields.parse()?,
+                high: fields.parse()?,
+                low: fields.parse()?,
+                close: fields.parse()?,
+                volume: fields.parse()?,
+                accumulated_volume: fields.parse()?,
+                vwap: fields.parse()?,
+                average: fields.parse()?,
+                day_open: fields.parse_opt()?,
+                average_trade_size: fields.parse_opt()?,
+            })
+        }
+    }
+}
+
+/// Writes rows of a single type as CSV, starting with a header row.
+pub struct CsvWriter<T, W: Write> {
+    writer: csv::Writer<W>,
+    options: CsvOptions,
+    header_written: bool,
+    row: PhantomData<T>,
+}
+
+impl<T: CsvRecord, W: Write> CsvWriter<T, W> {
+    pub fn new(writer: W, options: CsvOptions) -> Self {
+        Self {
+            writer: csv::WriterBuilder::new()
+                .delimiter(options.delimiter)
+                .from_writer(writer),
+            options,
+            header_written: false,
+            row: PhantomData,
+        }
+    }
+
+    pub fn write(&mut self, row: &T) -> Result<()> {
+        if !self.header_written {
+            self.writer.write_record(T::header())?;
+            self.header_written = true;
+        }
+        self.writer.write_record(row.to_fields(&self.options))?;
+        Ok(())
+    }
+
+    /// Write every row of every page in the stream, returning the number of rows written.
+    pub async fn write_pages<P, E, S>(&mut self, pages: S) -> Result<usize>
+    where
+        P: Page<Row = T>,
+        E: Into<Error>,
+        S: Stream<Item = std::result::Result<P, E>>,
+    {
+        let mut pages = Box::pin(pages);
+        let mut rows = 0;
+        while let Some(page) = pages.next().await {
+            for row in page.map_err(Into::into)?.into_rows() {
+                self.write(&row)?;
+                rows += 1;
+            }
+        }
+        self.flush()?;
+        Ok(rows)
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        Ok(self.writer.flush()?)
+    }
+
+    pub fn into_inner(self) -> Result<W> {
+        self.writer
+            .into_inner()
+            .map_err(|e| Error::Io(e.into_error()))
+    }
+}
+
+/// Writes the trades, quotes and aggregates in a stream of WebSocket messages to separate CSV
+/// outputs. Messages without an output, and status messages, are skipped.
+#[cfg(feature = "ws")]
+pub struct CsvMessageWriter<W: Write> {
+    options: CsvOptions,
+    trades: Option<CsvWriter<crate::ws::Trade, W>>,
+    quotes: Option<CsvWriter<crate::ws::Quote, W>>,
+    aggregates: Option<CsvWriter<crate::ws::Aggregate, W>>,
+}
+
+#[cfg(feature = "ws")]
+impl<W: Write> CsvMessageWriter<W> {
+    pub fn new(options: CsvOptions) -> Self {
+        Self {
+            options,
+            trades: None,
+            quotes: None,
+            aggregates: None,
+        }
+    }
+
+    pub fn trades(mut self, writer: W) -> Self {
+        self.trades = Some(CsvWriter::new(writer, self.options));
+        self
+    }
+
+    pub fn quotes(mut self, writer: W) -> Self {
+        self.quotes = Some(CsvWriter::new(writer, self.options));
+        self
+    }
+
+    /// Both minute and second aggregates are written to the same output.
+    pub fn aggregates(mut self, writer: W) -> Self {
+        self.aggregates = Some(CsvWriter::new(writer, self.options));
+        self
+    }
+
+    pub fn write(&mut self, message: &crate::ws::PolygonMessage) -> Result<()> {
+        use crate::ws::PolygonMessage;
+        match message {
+            PolygonMessage::Trade(t) => self.trades.as_mut().map(|w| w.write(t)),
+            PolygonMessage::Quote(q) => self.quotes.as_mut().map(|w| w.write(q)),
+            PolygonMessage::Minute(a) | PolygonMessage::Second(a) => {
+                self.aggregates.as_mut().map(|w| w.write(a))
+            }
+            PolygonMessage::Status { .. } => None,
+        }
+        .unwrap_or(Ok(()))
+    }
+
+    /// Write messages until the stream ends or returns an error.
+    pub async fn write_stream<S>(&mut self, messages: S) -> Result<()>
+    where
+        S: Stream<Item = Result<crate::ws::PolygonMessage>>,
+    {
+        let mut messages = Box::pin(messages);
+        while let Some(message) = messages.next().await {
+            self.write(&message?)?;
+        }
+        self.flush()
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        if let Some(w) = self.trades.as_mut() {
+            w.flush()?;
+        }
+        if let Some(w) = self.quotes.as_mut() {
+            w.flush()?;
+        }
+        if let Some(w) = self.aggregates.as_mut() {
+            w.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads rows written by a [`CsvWriter`] with the same options.
+pub struct CsvReader<T, R: Read> {
+    reader: csv::Reader<R>,
+    options: CsvOptions,
+    record: csv::StringRecord,
+    row: PhantomData<T>,
+}
+
+impl<T: CsvRecord, R: Read> CsvReader<T, R> {
+    /// Fails if the header does not match the columns of `T`.
+    pub fn new(reader: R, options: CsvOptions) -> Result<Self> {
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(options.delimiter)
+            .from_reader(reader);
+        let header = reader.headers()?;
+        if !header.iter().eq(T::header().iter().copied()) {
+            return Err(Error::InvalidField {
+                column: "header",
+                value: header.iter().collect::<Vec<_>>().join(","),
+            });
+        }
+        Ok(Self {
+            reader,
+            options,
+            record: csv::StringRecord::new(),
+            row: PhantomData,
+        })
+    }
+}
+
+impl<T: CsvRecord, R: Read> Iterator for CsvReader<T, R> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Result<T>> {
+        match self.reader.read_record(&mut self.record) {
+            Ok(true) => Some(T::from_fields(&mut Fields {
+                record: &self.record,
+                header: T::header(),
+                options: &self.options,
+                index: 0,
+            })),
+            Ok(false) => None,
+            Err(e) => Some(Err(e.into())),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "ws"))]
+mod test {
+    use super::*;
+    use crate::common::{Tape, TradeCondition};
+    use crate::ws::{AskQuote, PolygonMessage, Quote, Trade};
+    use rust_decimal_macros::dec;
+
+    fn messages() -> Vec<PolygonMessage> {
+        let timestamp = Utc.with_ymd_and_hms(2021, 3, 1, 14, 30, 0).unwrap();
+        vec![
+            PolygonMessage::Trade(Trade {
+                symbol: "MSFT".into(),
+                exchange_id: 4,
+                trade_id: "12345".into(),
+                tape: Tape::C,
+                price: dec!(114.125),
+                size: 100,
+                conditions: vec![TradeCondition::RegularSale, TradeCondition::FormT],
+                timestamp,
+            }),
+            PolygonMessage::Quote(Quote {
+                symbol: "MSFT".into(),
+                bid_quote: None,
+                ask_quote: Some(AskQuote {
+                    exchange_id: 11,
+                    price: dec!(114.13),
+                    size: 3,
+                }),
+                condition: None,
+                timestamp,
+            }),
+        ]
+    }
+
+    #[tokio::test]
+    async fn roundtrip_messages() {
+        let options = CsvOptions {
+            timestamps: TimestampFormat::EpochNanos,
+            ..Default::default()
+        };
+        let mut writer = CsvMessageWriter::new(options)
+            .trades(Vec::new())
+            .quotes(Vec::new());
+        writer
+            .write_stream(stream::iter(messages().into_iter().map(Ok)))
+            .await
+            .unwrap();
+        let trades = writer.trades.take().unwrap().into_inner().unwrap();
+        let quotes = writer.quotes.take().unwrap().into_inner().unwrap();
+        assert_eq!(
+            String::from_utf8(trades.clone()).unwrap(),
+            "symbol,timestamp,exchange_id,trade_id,tape,price,size,conditions\n\
+             MSFT,1614609000000000000,4,12345,3,114.125,100,0;12\n"
+        );
+        assert_eq!(
+            String::from_utf8(quotes.clone()).unwrap().lines().nth(1),
+            Some("MSFT,1614609000000000000,,,,11,114.13,3,")
+        );
+
+        let trades: Vec<Trade> = CsvReader::new(trades.as_slice(), options)
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        let quotes: Vec<Quote> = CsvReader::new(quotes.as_slice(), options)
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        let messages = messages();
+        assert_eq!(PolygonMessage::Trade(trades[0].clone()), messages[0]);
+        assert_eq!(PolygonMessage::Quote(quotes[0].clone()), messages[1]);
+    }
+
+    #[test]
+    fn rejects_mismatched_header() {
+        let data = "symbol,timestamp\nMSFT,1614609000000000000\n";
+        assert!(CsvReader::<Trade, _>::new(data.as_bytes(), Default::default()).is_err());
+    }
+}
@@ -0,0 +1,36 @@
+//! Writing market data as CSV or newline-delimited JSON, and reading it back into the crate's
+//! types.
+//!
+//! Writers accept individual rows, REST response pages or streams of either, as well as streams
+//! of [`PolygonMessage`](crate::ws::PolygonMessage)s.
+
+mod delimited;
+mod ndjson;
+
+pub use delimited::*;
+pub use ndjson::*;
+
+/// A REST response page made up of rows that can be exported.
+pub trait Page {
+    type Row;
+
+    fn into_rows(self) -> Vec<Self::Row>;
+}
+
+#[cfg(feature = "rest")]
+impl Page for crate::rest::AggregateWrapper {
+    type Row = crate::rest::Aggregate;
+
+    fn into_rows(self) -> Vec<Self::Row> {
+        self.results
+    }
+}
+
+#[cfg(feature = "rest")]
+impl Page for crate::rest::QuoteWrapper {
+    type Row = crate::rest::Quote;
+
+    fn into_rows(self) -> Vec<Self::Row> {
+        self.results
+    }
+}
@@ -0,0 +1,133 @@
+use super::Page;
+use crate::errors::{Error, Result};
+use futures::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+use std::io::{BufRead, Write};
+use std::marker::PhantomData;
+
+/// Writes rows as newline-delimited JSON, one object per line, in the same format as the REST and
+/// WebSocket APIs.
+pub struct NdjsonWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> NdjsonWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    pub fn write<T: Serialize>(&mut self, row: &T) -> Result<()> {
+        serde_json::to_writer(&mut self.writer, row).map_err(|error| Error::Serde {
+            error,
+            msg: "Failed to write NDJSON row".into(),
+        })?;
+        self.writer.write_all(b"\n")?;
+        Ok(())
+    }
+
+    /// Write every row of every page in the stream, returning the number of rows written.
+    pub async fn write_pages<P, E, S>(&mut self, pages: S) -> Result<usize>
+    where
+        P: Page,
+        P::Row: Serialize,
+        E: Into<Error>,
+        S: Stream<Item = std::result::Result<P, E>>,
+    {
+        let mut pages = Box::pin(pages);
+        let mut rows = 0;
+        while let Some(page) = pages.next().await {
+            for row in page.map_err(Into::into)?.into_rows() {
+                self.write(&row)?;
+                rows += 1;
+            }
+        }
+        self.flush()?;
+        Ok(rows)
+    }
+
+    /// Write messages until the stream ends or returns an error. Status messages are included,
+    /// so the output can be read back as [`PolygonMessage`](crate::ws::PolygonMessage)s.
+    #[cfg(feature = "ws")]
+    pub async fn write_messages<S>(&mut self, messages: S) -> Result<()>
+    where
+        S: Stream<Item = Result<crate::ws::PolygonMessage>>,
+    {
+        let mut messages = Box::pin(messages);
+        while let Some(message) = messages.next().await {
+            self.write(&message?)?;
+        }
+        self.flush()
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        Ok(self.writer.flush()?)
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Reads rows written by an [`NdjsonWriter`]. Blank lines are skipped.
+pub struct NdjsonReader<T, R: BufRead> {
+    lines: std::io::Lines<R>,
+    row: PhantomData<T>,
+}
+
+impl<T: DeserializeOwned, R: BufRead> NdjsonReader<T, R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            lines: reader.lines(),
+            row: PhantomData,
+        }
+    }
+}
+
+impl<T: DeserializeOwned, R: BufRead> Iterator for NdjsonReader<T, R> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Result<T>> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e.into())),
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            return Some(serde_json::from_str(&line).map_err(|error| Error::Serde {
+                error,
+                msg: format!("Failed to parse NDJSON row: {}", line),
+            }));
+        }
+    }
+}
+
+#[cfg(all(test, feature = "rest"))]
+mod test {
+    use super::*;
+    use crate::rest::{AggregateWrapper, Quote};
+
+    #[tokio::test]
+    async fn roundtrip_pages() {
+        let page: AggregateWrapper = serde_json::from_str(r#"{"ticker":"AAPL","status":"OK","queryCount":2,"resultsCount":2,"adjusted":true,"results":[{"v":70790813,"vw":131.6292,"o":130.465,"c":131.96,"h":133.41,"l":129.47,"t":1614574800000,"n":520464},{"v":55740326,"vw":127.3829,"o":130.43,"c":125.12,"h":130.73,"l":125.05,"t":1614661200000,"n":453712}],"request_id":"6a7e466379af0a71039d60cc78e72282"}"#).unwrap();
+        let mut writer = NdjsonWriter::new(Vec::new());
+        let rows = writer
+            .write_pages(stream::iter(vec![Ok::<_, Error>(page.clone())]))
+            .await
+            .unwrap();
+        assert_eq!(rows, 2);
+        let data = writer.into_inner();
+        assert_eq!(data.iter().filter(|b| **b == b'\n').count(), 2);
+
+        let aggs: Vec<crate::rest::Aggregate> = NdjsonReader::new(data.as_slice())
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(aggs.len(), 2);
+        assert_eq!(aggs[1].t, page.results[1].t);
+        assert_eq!(aggs[1].c, page.results[1].c);
+
+        let res: Option<Result<Quote>> = NdjsonReader::new("\n{}\n".as_bytes()).next();
+        assert!(matches!(res, Some(Err(Error::Serde { .. }))));
+    }
+}
@@ -0,0 +1,543 @@
+//! Incremental technical indicators computed locally, one bar at a time.
+//!
+//! Every indicator implements [`Indicator`], which is fed bars through [`Indicator::push`] and
+//! returns `None` until enough bars have been seen to produce a value. Bars are anything that
+//! implements [`Bar`], which includes both the REST and the WebSocket aggregates.
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::US::Eastern;
+use rust_decimal::prelude::*;
+use std::collections::VecDeque;
+use std::num::NonZeroUsize;
+
+/// An OHLCV bar that can be fed into an [`Indicator`].
+pub trait Bar {
+    fn open(&self) -> Decimal;
+    fn high(&self) -> Decimal;
+    fn low(&self) -> Decimal;
+    fn close(&self) -> Decimal;
+    fn volume(&self) -> Decimal;
+    /// The volume-weighted average price of the bar, if known.
+    fn vwap(&self) -> Option<Decimal>;
+    /// The start of the bar.
+    fn timestamp(&self) -> DateTime<Utc>;
+}
+
+#[cfg(feature = "rest")]
+impl Bar for crate::rest::Aggregate {
+    fn open(&self) -> Decimal {
+        self.o
+    }
+
+    fn high(&self) -> Decimal {
+        self.h
+    }
+
+    fn low(&self) -> Decimal {
+        self.l
+    }
+
+    fn close(&self) -> Decimal {
+        self.c
+    }
+
+    fn volume(&self) -> Decimal {
+        self.v
+    }
+
+    fn vwap(&self) -> Option<Decimal> {
+        self.vw
+    }
+
+    fn timestamp(&self) -> DateTime<Utc> {
+        self.t
+    }
+}
+
+#[cfg(feature = "ws")]
+impl Bar for crate::ws::Aggregate {
+    fn open(&self) -> Decimal {
+        self.open
+    }
+
+    fn high(&self) -> Decimal {
+        self.high
+    }
+
+    fn low(&self) -> Decimal {
+        self.low
+    }
+
+    fn close(&self) -> Decimal {
+        self.close
+    }
+
+    fn volume(&self) -> Decimal {
+        self.volume.into()
+    }
+
+    fn vwap(&self) -> Option<Decimal> {
+        Some(self.vwap)
+    }
+
+    fn timestamp(&self) -> DateTime<Utc> {
+        self.start_timestamp
+    }
+}
+
+pub trait Indicator {
+    type Output;
+
+    /// Update the indicator with the next bar, returning the new value once the indicator is
+    /// warmed up.
+    fn push<B: Bar>(&mut self, bar: &B) -> Option<Self::Output>;
+
+    /// Clear all state, as if no bars had been pushed.
+    fn reset(&mut self);
+}
+
+/// Simple moving average of the close.
+#[derive(Debug, Clone)]
+pub struct Sma {
+    period: usize,
+    window: VecDeque<Decimal>,
+    sum: Decimal,
+}
+
+impl Sma {
+    pub fn new(period: NonZeroUsize) -> Self {
+        let period = period.get();
+        Self {
+            period,
+            window: VecDeque::with_capacity(period),
+            sum: Decimal::ZERO,
+        }
+    }
+
+    fn update(&mut self, value: Decimal) -> Option<Decimal> {
+        self.window.push_back(value);
+        self.sum += value;
+        if self.window.len() > self.period {
+            self.sum -= self.window.pop_front().unwrap();
+        }
+        (self.window.len() == self.period).then(|| self.sum / Decimal::from(self.period))
+    }
+}
+
+impl Indicator for Sma {
+    type Output = Decimal;
+
+    fn push<B: Bar>(&mut self, bar: &B) -> Option<Decimal> {
+        self.update(bar.close())
+    }
+
+    fn reset(&mut self) {
+        self.window.clear();
+        self.sum = Decimal::ZERO;
+    }
+}
+
+/// Exponential moving average of the close, seeded with the simple average of the first `period`
+/// bars.
+#[derive(Debug, Clone)]
+pub struct Ema {
+    alpha: Decimal,
+    seed: Sma,
+    value: Option<Decimal>,
+}
+
+impl Ema {
+    pub fn new(period: NonZeroUsize) -> Self {
+        Self {
+            alpha: Decimal::TWO / Decimal::from(period.get() + 1),
+            seed: Sma::new(period),
+            value: None,
+        }
+    }
+
+    fn update(&mut self, value: Decimal) -> Option<Decimal> {
+        self.value = match self.value {
+            Some(prev) => Some(prev + self.alpha * (value - prev)),
+            None => self.seed.update(value),
+        };
+        self.value
+    }
+}
+
+impl Indicator for Ema {
+    type Output = Decimal;
+
+    fn push<B: Bar>(&mut self, bar: &B) -> Option<Decimal> {
+        self.update(bar.close())
+    }
+
+    fn reset(&mut self) {
+        self.seed.reset();
+        self.value = None;
+    }
+}
+
+/// Session volume-weighted average price, reset at the start of each trading day in US/Eastern.
+///
+/// Each bar contributes its own `vw` weighted by its volume, falling back to the typical price
+/// `(h + l + c) / 3` for bars without one, so the result can be compared directly against the
+/// day's VWAP reported by Polygon.
+#[derive(Debug, Clone, Default)]
+pub struct Vwap {
+    session: Option<NaiveDate>,
+    price_volume: Decimal,
+    volume: Decimal,
+}
+
+impl Vwap {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl Indicator for Vwap {
+    type Output = Decimal;
+
+    fn push<B: Bar>(&mut self, bar: &B) -> Option<Decimal> {
+        let session = bar.timestamp().with_timezone(&Eastern).date_naive();
+        if self.session != Some(session) {
+            self.reset();
+            self.session = Some(session);
+        }
+        let price = bar
+            .vwap()
+            .unwrap_or_else(|| (bar.high() + bar.low() + bar.close()) / Decimal::from(3));
+        self.price_volume += price * bar.volume();
+        self.volume += bar.volume();
+        (!self.volume.is_zero()).then(|| self.price_volume / self.volume)
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Wilder's smoothed average, seeded with the simple average of the first `period` values.
+#[derive(Debug, Clone)]
+struct Wilder {
+    period: Decimal,
+    seed: Sma,
+    value: Option<Decimal>,
+}
+
+impl Wilder {
+    fn new(period: NonZeroUsize) -> Self {
+        Self {
+            period: Decimal::from(period.get()),
+            seed: Sma::new(period),
+            value: None,
+        }
+    }
+
+    fn update(&mut self, value: Decimal) -> Option<Decimal> {
+        self.value = match self.value {
+            Some(prev) => Some((prev * (self.period - Decimal::ONE) + value) / self.period),
+            None => self.seed.update(value),
+        };
+        self.value
+    }
+
+    fn reset(&mut self) {
+        self.seed.reset();
+        self.value = None;
+    }
+}
+
+/// Relative strength index of the close, using Wilder's smoothing.
+#[derive(Debug, Clone)]
+pub struct Rsi {
+    prev_close: Option<Decimal>,
+    gain: Wilder,
+    loss: Wilder,
+}
+
+impl Rsi {
+    pub fn new(period: NonZeroUsize) -> Self {
+        Self {
+            prev_close: None,
+            gain: Wilder::new(period),
+            loss: Wilder::new(period),
+        }
+    }
+}
+
+impl Indicator for Rsi {
+    type Output = Decimal;
+
+    fn push<B: Bar>(&mut self, bar: &B) -> Option<Decimal> {
+        let close = bar.close();
+        let change = close - self.prev_close.replace(close)?;
+        let gain = self.gain.update(change.max(Decimal::ZERO));
+        let loss = self.loss.update((-change).max(Decimal::ZERO));
+        let (gain, loss) = (gain?, loss?);
+        // A flat series has neither gains nor losses, which is neutral rather than overbought
+        if loss.is_zero() {
+            return Some(if gain.is_zero() {
+                Decimal::ONE_HUNDRED / Decimal::TWO
+            } else {
+                Decimal::ONE_HUNDRED
+            });
+        }
+        let rs = gain / loss;
+        Some(Decimal::ONE_HUNDRED - Decimal::ONE_HUNDRED / (Decimal::ONE + rs))
+    }
+
+    fn reset(&mut self) {
+        self.prev_close = None;
+        self.gain.reset();
+        self.loss.reset();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MacdOutput {
+    pub macd: Decimal,
+    pub signal: Decimal,
+    pub histogram: Decimal,
+}
+
+/// Moving average convergence/divergence of the close.
+#[derive(Debug, Clone)]
+pub struct Macd {
+    fast: Ema,
+    slow: Ema,
+    signal: Ema,
+}
+
+impl Macd {
+    pub fn new(fast: NonZeroUsize, slow: NonZeroUsize, signal: NonZeroUsize) -> Self {
+        Self {
+            fast: Ema::new(fast),
+            slow: Ema::new(slow),
+            signal: Ema::new(signal),
+        }
+    }
+}
+
+impl Default for Macd {
+    fn default() -> Self {
+        let period = |n| NonZeroUsize::new(n).unwrap();
+        Self::new(period(12), period(26), period(9))
+    }
+}
+
+impl Indicator for Macd {
+    type Output = MacdOutput;
+
+    fn push<B: Bar>(&mut self, bar: &B) -> Option<MacdOutput> {
+        let fast = self.fast.update(bar.close());
+        let slow = self.slow.update(bar.close());
+        let macd = fast? - slow?;
+        let signal = self.signal.update(macd)?;
+        Some(MacdOutput {
+            macd,
+            signal,
+            histogram: macd - signal,
+        })
+    }
+
+    fn reset(&mut self) {
+        self.fast.reset();
+        self.slow.reset();
+        self.signal.reset();
+    }
+}
+
+/// Average true range, using Wilder's smoothing.
+#[derive(Debug, Clone)]
+pub struct Atr {
+    prev_close: Option<Decimal>,
+    average: Wilder,
+}
+
+impl Atr {
+    pub fn new(period: NonZeroUsize) -> Self {
+        Self {
+            prev_close: None,
+            average: Wilder::new(period),
+        }
+    }
+}
+
+impl Indicator for Atr {
+    type Output = Decimal;
+
+    fn push<B: Bar>(&mut self, bar: &B) -> Option<Decimal> {
+        let range = bar.high() - bar.low();
+        let true_range = match self.prev_close.replace(bar.close()) {
+            Some(prev) => range
+                .max((bar.high() - prev).abs())
+                .max((bar.low() - prev).abs()),
+            None => range,
+        };
+        self.average.update(true_range)
+    }
+
+    fn reset(&mut self) {
+        self.prev_close = None;
+        self.average.reset();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BollingerOutput {
+    pub upper: Decimal,
+    pub middle: Decimal,
+    pub lower: Decimal,
+}
+
+/// Bollinger bands around the simple moving average of the close, `k` population standard
+/// deviations wide.
+#[derive(Debug, Clone)]
+pub struct Bollinger {
+    k: Decimal,
+    sma: Sma,
+}
+
+impl Bollinger {
+    pub fn new(period: NonZeroUsize, k: Decimal) -> Self {
+        Self {
+            k,
+            sma: Sma::new(period),
+        }
+    }
+}
+
+impl Indicator for Bollinger {
+    type Output = BollingerOutput;
+
+    fn push<B: Bar>(&mut self, bar: &B) -> Option<BollingerOutput> {
+        let middle = self.sma.update(bar.close())?;
+        let variance = self
+            .sma
+            .window
+            .iter()
+            .map(|x| (x - middle) * (x - middle))
+            .sum::<Decimal>()
+            / Decimal::from(self.sma.period);
+        let width = self.k * variance.sqrt()?;
+        Some(BollingerOutput {
+            upper: middle + width,
+            middle,
+            lower: middle - width,
+        })
+    }
+
+    fn reset(&mut self) {
+        self.sma.reset();
+    }
+}
+
+#[cfg(all(test, feature = "rest"))]
+mod test {
+    use super::*;
+    use crate::rest::Aggregate;
+    use chrono::{Duration, TimeZone};
+    use rust_decimal_macros::dec;
+
+    fn bars(closes: &[Decimal]) -> Vec<Aggregate> {
+        let start = Utc.with_ymd_and_hms(2021, 6, 1, 13, 30, 0).unwrap();
+        closes
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| Aggregate {
+                o: c,
+                h: c + dec!(1),
+                l: c - dec!(1),
+                c,
+                v: dec!(100),
+                vw: None,
+                t: start + Duration::minutes(i as i64),
+                n: None,
+            })
+            .collect()
+    }
+
+    fn period(n: usize) -> NonZeroUsize {
+        NonZeroUsize::new(n).unwrap()
+    }
+
+    fn run<I: Indicator>(indicator: &mut I, bars: &[Aggregate]) -> Vec<Option<I::Output>> {
+        bars.iter().map(|b| indicator.push(b)).collect()
+    }
+
+    #[test]
+    fn moving_averages() {
+        let bars = bars(&[dec!(1), dec!(2), dec!(3), dec!(4), dec!(5)]);
+        assert_eq!(
+            run(&mut Sma::new(period(3)), &bars),
+            vec![None, None, Some(dec!(2)), Some(dec!(3)), Some(dec!(4))]
+        );
+        assert_eq!(
+            run(&mut Ema::new(period(3)), &bars),
+            vec![None, None, Some(dec!(2)), Some(dec!(3)), Some(dec!(4))]
+        );
+        let mut ema = Ema::new(period(3));
+        run(&mut ema, &bars);
+        ema.reset();
+        assert_eq!(ema.push(&bars[0]), None);
+    }
+
+    #[test]
+    fn rsi_and_atr() {
+        // A flat series is neutral
+        let flat = bars(&[dec!(10), dec!(10), dec!(10)]);
+        assert_eq!(run(&mut Rsi::new(period(2)), &flat)[2], Some(dec!(50)));
+
+        let bars = bars(&[dec!(10), dec!(12), dec!(11), dec!(13)]);
+        let rsi = run(&mut Rsi::new(period(2)), &bars);
+        assert_eq!(rsi[..2], [None, None]);
+        // Average gain of 1 and average loss of 0.5
+        assert_eq!(rsi[2].unwrap().round_dp(6), dec!(66.666667));
+        // Average gain of 1.5 and average loss of 0.25
+        assert_eq!(rsi[3].unwrap().round_dp(6), dec!(85.714286));
+
+        let atr = run(&mut Atr::new(period(2)), &bars);
+        assert_eq!(
+            atr,
+            vec![None, Some(dec!(2.5)), Some(dec!(2.25)), Some(dec!(2.625))]
+        );
+    }
+
+    #[test]
+    fn session_vwap() {
+        let mut bars = bars(&[dec!(10), dec!(13)]);
+        bars[0].vw = Some(dec!(11));
+        let mut vwap = Vwap::new();
+        assert_eq!(vwap.push(&bars[0]), Some(dec!(11)));
+        assert_eq!(vwap.push(&bars[1]), Some(dec!(12)));
+
+        bars[1].t += Duration::days(1);
+        vwap.push(&bars[0]);
+        assert_eq!(vwap.push(&bars[1]), Some(dec!(13)));
+    }
+
+    #[test]
+    fn macd_and_bollinger() {
+        let bars = bars(&[dec!(1), dec!(2), dec!(3), dec!(4), dec!(5)]);
+        let macd = run(&mut Macd::new(period(2), period(3), period(2)), &bars);
+        assert_eq!(macd[..3], [None, None, None]);
+        assert_eq!(
+            macd[3],
+            Some(MacdOutput {
+                macd: dec!(0.5),
+                signal: dec!(0.5),
+                histogram: dec!(0),
+            })
+        );
+
+        let bands = run(&mut Bollinger::new(period(2), dec!(2)), &bars);
+        assert_eq!(
+            bands[1],
+            Some(BollingerOutput {
+                upper: dec!(2.5),
+                middle: dec!(1.5),
+                lower: dec!(0.5),
+            })
+        );
+    }
+}
@@ -1,7 +1,20 @@
 extern crate chrono;
 extern crate chrono_tz;
+#[cfg(any(feature = "rest", feature = "ws"))]
+mod client;
+#[cfg(feature = "columnar")]
+pub mod columnar;
+pub mod common;
+#[cfg(any(feature = "rest", feature = "ws"))]
+pub mod config;
 pub mod errors;
+#[cfg(feature = "export")]
+pub mod export;
+pub mod indicators;
 #[cfg(feature = "rest")]
 pub mod rest;
 #[cfg(feature = "ws")]
 pub mod ws;
+
+#[cfg(any(feature = "rest", feature = "ws"))]
+pub use client::Polygon;
@@ -0,0 +1,349 @@
+use super::{Aggregate, StockDividend, StockSplit};
+use crate::common::Ticker;
+use crate::errors::{Error, Result};
+use chrono::NaiveDate;
+use chrono_tz::US::Eastern;
+use rust_decimal::prelude::*;
+use std::collections::BTreeMap;
+
+/// The corporate actions to account for when back-adjusting a price series.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdjustmentMode {
+    /// Only adjust for stock splits.
+    Splits,
+    /// Only adjust for cash dividends.
+    Dividends,
+    /// Adjust for both splits and dividends, producing a total-return series.
+    TotalReturn,
+}
+
+/// Cumulative adjustment factors for a single trading date.
+///
+/// Prices on `date` are multiplied by `split * dividend` (or either factor alone, depending on
+/// the [`AdjustmentMode`]), while volumes are divided by `split`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdjustmentFactor {
+    pub date: NaiveDate,
+    pub split: Decimal,
+    pub dividend: Decimal,
+}
+
+impl AdjustmentFactor {
+    pub fn price(&self, mode: AdjustmentMode) -> Decimal {
+        match mode {
+            AdjustmentMode::Splits => self.split,
+            AdjustmentMode::Dividends => self.dividend,
+            AdjustmentMode::TotalReturn => self.split * self.dividend,
+        }
+    }
+
+    pub fn volume(&self, mode: AdjustmentMode) -> Decimal {
+        match mode {
+            AdjustmentMode::Dividends => Decimal::ONE,
+            AdjustmentMode::Splits | AdjustmentMode::TotalReturn => Decimal::ONE / self.split,
+        }
+    }
+}
+
+/// Back-adjusts unadjusted aggregate bars for splits and dividends.
+///
+/// Bars are expected to be unadjusted (see `GetAggregate::unadjusted`) and sorted in ascending
+/// order. Every event applies to all bars strictly before its ex-date, so the most recent prices
+/// are left untouched. Dividend factors follow the usual `1 - amount / close` convention, where
+/// `close` is the last close before the ex-date. Dividends whose ex-date falls outside of the
+/// series therefore have no reference close and are ignored, while dividends at least as large as
+/// their reference close are rejected.
+#[derive(Debug, Clone, Default)]
+pub struct Adjuster {
+    splits: Vec<(NaiveDate, Decimal)>,
+    dividends: Vec<(NaiveDate, Decimal, Ticker)>,
+}
+
+impl Adjuster {
+    /// Fails if a split ratio is zero, a dividend amount is negative, or a split or dividend can't
+    /// be represented as a decimal.
+    pub fn new(splits: &[StockSplit], dividends: &[StockDividend]) -> Result<Self> {
+        let mut splits = splits
+            .iter()
+            .map(|s| match Decimal::from_f64(s.ratio) {
+                Some(ratio) if !ratio.is_zero() => Ok((s.ex_date, ratio)),
+                _ => Err(invalid("split", &s.ticker, s.ex_date, s.ratio)),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut dividends = dividends
+            .iter()
+            .map(|d| match Decimal::from_f64(d.amount) {
+                Some(amount) if !amount.is_sign_negative() => {
+                    Ok((d.ex_date, amount, d.ticker.clone()))
+                }
+                _ => Err(invalid("dividend", &d.ticker, d.ex_date, d.amount)),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        splits.sort_by_key(|(ex_date, _)| *ex_date);
+        dividends.sort_by_key(|(ex_date, _, _)| *ex_date);
+        Ok(Self { splits, dividends })
+    }
+
+    /// Return the cumulative adjustment factors for every distinct date in `bars`, in ascending
+    /// order. Fails if a dividend is at least as large as the close before its ex-date.
+    pub fn factors(&self, bars: &[Aggregate]) -> Result<Vec<AdjustmentFactor>> {
+        Ok(self.factors_by_date(bars)?.into_values().collect())
+    }
+
+    fn factors_by_date(&self, bars: &[Aggregate]) -> Result<BTreeMap<NaiveDate, AdjustmentFactor>> {
+        let closes: BTreeMap<NaiveDate, Decimal> =
+            bars.iter().map(|bar| (trading_date(bar), bar.c)).collect();
+
+        let mut dividend_factors = Vec::new();
+        for (ex_date, amount, ticker) in &self.dividends {
+            let close = match closes.range(..ex_date).next_back() {
+                Some((_, close)) if closes.range(ex_date..).next().is_some() => close,
+                _ => continue,
+            };
+            if amount.is_zero() {
+                continue;
+            }
+            // A factor of zero or less would zero or flip the sign of every earlier price
+            if amount >= close {
+                let value = format!("{} against a close of {}", amount, close);
+                return Err(invalid("dividend", ticker, *ex_date, value));
+            }
+            dividend_factors.push((*ex_date, Decimal::ONE - amount / close));
+        }
+
+        // Walk back from the most recent date, accumulating the events after each date
+        let mut splits = self.splits.iter().rev().peekable();
+        let mut dividends = dividend_factors.iter().rev().peekable();
+        let (mut split, mut dividend) = (Decimal::ONE, Decimal::ONE);
+        let factors = closes
+            .keys()
+            .rev()
+            .map(|date| {
+                while let Some((_, ratio)) = splits.next_if(|(ex_date, _)| ex_date > date) {
+                    split *= ratio;
+                }
+                while let Some((_, factor)) = dividends.next_if(|(ex_date, _)| ex_date > date) {
+                    dividend *= factor;
+                }
+                let factor = AdjustmentFactor {
+                    date: *date,
+                    split,
+                    dividend,
+                };
+                (*date, factor)
+            })
+            .collect();
+        Ok(factors)
+    }
+
+    /// Return a back-adjusted copy of `bars`, failing like [`Adjuster::factors`].
+    pub fn adjust(&self, bars: &[Aggregate], mode: AdjustmentMode) -> Result<Vec<Aggregate>> {
+        let factors = self.factors_by_date(bars)?;
+        let bars = bars
+            .iter()
+            .map(|bar| {
+                let factor = match factors.get(&trading_date(bar)) {
+                    Some(factor) => factor,
+                    None => return bar.clone(),
+                };
+                let price = factor.price(mode);
+                let volume = factor.volume(mode);
+                Aggregate {
+                    o: bar.o * price,
+                    h: bar.h * price,
+                    l: bar.l * price,
+                    c: bar.c * price,
+                    v: bar.v * volume,
+                    vw: bar.vw.map(|vw| vw * price),
+                    t: bar.t,
+                    n: bar.n,
+                }
+            })
+            .collect();
+        Ok(bars)
+    }
+}
+
+fn invalid<V: ToString>(
+    action: &'static str,
+    ticker: &Ticker,
+    ex_date: NaiveDate,
+    value: V,
+) -> Error {
+    Error::InvalidCorporateAction {
+        action,
+        ticker: ticker.to_string(),
+        ex_date,
+        value: value.to_string(),
+    }
+}
+
+fn trading_date(bar: &Aggregate) -> NaiveDate {
+    bar.t.with_timezone(&Eastern).date_naive()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::TimeZone;
+    use rust_decimal_macros::dec;
+
+    fn bar(day: u32, close: Decimal) -> Aggregate {
+        let t = Eastern
+            .with_ymd_and_hms(2021, 3, day, 0, 0, 0)
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        Aggregate {
+            o: close,
+            h: close,
+            l: close,
+            c: close,
+            v: dec!(1000),
+            vw: Some(close),
+            t,
+            n: Some(10),
+        }
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2021, 3, day).unwrap()
+    }
+
+    fn adjuster() -> Adjuster {
+        let splits = vec![StockSplit {
+            forfactor: Some(2),
+            tofactor: Some(1),
+            ratio: 0.5,
+            declared_date: None,
+            ex_date: date(4),
+            payment_date: date(4),
+            ticker: "AAPL".into(),
+        }];
+        let dividends = vec![StockDividend {
+            amount: 1.0,
+            ex_date: date(2),
+            payment_date: date(10),
+            record_date: date(3),
+            ticker: "AAPL".into(),
+        }];
+        Adjuster::new(&splits, &dividends).unwrap()
+    }
+
+    #[test]
+    fn adjustment_factors() {
+        let bars = vec![
+            bar(1, dec!(100)),
+            bar(2, dec!(99)),
+            bar(3, dec!(102)),
+            bar(4, dec!(51)),
+        ];
+        let factors = adjuster().factors(&bars).unwrap();
+        assert_eq!(
+            factors,
+            vec![
+                AdjustmentFactor {
+                    date: date(1),
+                    split: dec!(0.5),
+                    dividend: dec!(0.99),
+                },
+                AdjustmentFactor {
+                    date: date(2),
+                    split: dec!(0.5),
+                    dividend: dec!(1),
+                },
+                AdjustmentFactor {
+                    date: date(3),
+                    split: dec!(0.5),
+                    dividend: dec!(1),
+                },
+                AdjustmentFactor {
+                    date: date(4),
+                    split: dec!(1),
+                    dividend: dec!(1),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn adjust_bars() {
+        let bars = vec![
+            bar(1, dec!(100)),
+            bar(2, dec!(99)),
+            bar(3, dec!(102)),
+            bar(4, dec!(51)),
+        ];
+        let adjuster = adjuster();
+
+        let splits = adjuster.adjust(&bars, AdjustmentMode::Splits).unwrap();
+        let closes: Vec<_> = splits.iter().map(|b| b.c).collect();
+        assert_eq!(closes, vec![dec!(50), dec!(49.5), dec!(51), dec!(51)]);
+        assert_eq!(splits[0].v, dec!(2000));
+        assert_eq!(splits[3].v, dec!(1000));
+
+        let dividends = adjuster.adjust(&bars, AdjustmentMode::Dividends).unwrap();
+        let closes: Vec<_> = dividends.iter().map(|b| b.c).collect();
+        assert_eq!(closes, vec![dec!(99), dec!(99), dec!(102), dec!(51)]);
+        assert_eq!(dividends[0].v, dec!(1000));
+
+        let total = adjuster.adjust(&bars, AdjustmentMode::TotalReturn).unwrap();
+        let closes: Vec<_> = total.iter().map(|b| b.c).collect();
+        assert_eq!(closes, vec![dec!(49.5), dec!(49.5), dec!(51), dec!(51)]);
+        assert_eq!(total[0].vw, Some(dec!(49.5)));
+    }
+
+    #[test]
+    fn ignores_dividends_outside_of_series() {
+        let bars = vec![bar(3, dec!(102)), bar(4, dec!(51))];
+        let factors = adjuster().factors(&bars).unwrap();
+        assert!(factors.iter().all(|f| f.dividend == Decimal::ONE));
+    }
+
+    #[test]
+    fn rejects_invalid_events() {
+        let split = StockSplit {
+            forfactor: None,
+            tofactor: None,
+            ratio: 0.0,
+            declared_date: None,
+            ex_date: date(4),
+            payment_date: date(4),
+            ticker: "AAPL".into(),
+        };
+        assert!(matches!(
+            Adjuster::new(&[split], &[]),
+            Err(Error::InvalidCorporateAction {
+                action: "split",
+                ..
+            })
+        ));
+
+        let dividend = |amount| StockDividend {
+            amount,
+            ex_date: date(2),
+            payment_date: date(10),
+            record_date: date(3),
+            ticker: "AAPL".into(),
+        };
+        for amount in [f64::NAN, -1.0] {
+            assert!(matches!(
+                Adjuster::new(&[], &[dividend(amount)]),
+                Err(Error::InvalidCorporateAction {
+                    action: "dividend",
+                    ..
+                })
+            ));
+        }
+
+        // A dividend as large as the previous close would zero the earlier prices
+        let bars = vec![bar(1, dec!(100)), bar(2, dec!(1))];
+        let adjuster = Adjuster::new(&[], &[dividend(100.0)]).unwrap();
+        assert!(matches!(
+            adjuster.adjust(&bars, AdjustmentMode::Dividends),
+            Err(Error::InvalidCorporateAction {
+                action: "dividend",
+                ..
+            })
+        ));
+    }
+}
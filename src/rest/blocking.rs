@@ -0,0 +1,439 @@
+//! A synchronous REST client for programs that can't run an async runtime.
+//!
+//! The client sends the same [`Request`] and [`PaginatedRequest`] types as the async clients, and
+//! returns paginated responses as iterators. It must not be used from within an async runtime.
+
+use super::http::format_request;
+use super::{
+    AggregateWrapper, ConditionsWrapper, DividendsWrapper, FinancialsWrapper, IndicatorWrapper,
+    QuoteWrapper, ResponseCache, RetryPolicy, SplitsWrapper, TickerNewsWrapper, TradeWrapper,
+};
+use crate::errors::{Error, Result};
+use std::time::Duration;
+use tracing::warn;
+use vila::pagination::{PaginatedRequest, Paginator, RequestModifier, State};
+use vila::Request;
+
+pub struct ClientBuilder {
+    base_url: String,
+    token: String,
+    retry_policy: RetryPolicy,
+    timeout: Option<Duration>,
+    cache: Option<ResponseCache>,
+}
+
+impl ClientBuilder {
+    pub fn base_url<T: ToString>(mut self, base_url: T) -> Self {
+        self.base_url = base_url.to_string();
+        self
+    }
+
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Serve responses from, and store responses in, an on-disk cache. The cache can be shared
+    /// with a [`ThrottledClient`](super::ThrottledClient).
+    pub fn cache(mut self, cache: ResponseCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// The timeout for each attempt of a request. Defaults to 30 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn build(self) -> Client {
+        let mut http = reqwest::blocking::Client::builder();
+        if let Some(timeout) = self.timeout {
+            http = http.timeout(timeout);
+        }
+        Client {
+            http: http.build().expect("Failed to initialize the HTTP client"),
+            base_url: self.base_url,
+            token: self.token,
+            retry_policy: self.retry_policy,
+            cache: self.cache,
+        }
+    }
+}
+
+/// A blocking REST client, which retries rate limited and failed requests and caches responses
+/// like [`ThrottledClient`](super::ThrottledClient).
+pub struct Client {
+    http: reqwest::blocking::Client,
+    base_url: String,
+    token: String,
+    retry_policy: RetryPolicy,
+    cache: Option<ResponseCache>,
+}
+
+impl Client {
+    pub fn new<T: ToString>(token: T) -> Self {
+        Self::builder(token).build()
+    }
+
+    pub fn builder<T: ToString>(token: T) -> ClientBuilder {
+        ClientBuilder {
+            base_url: "https://api.polygon.io".into(),
+            token: token.to_string(),
+            retry_policy: Default::default(),
+            timeout: None,
+            cache: None,
+        }
+    }
+
+    fn format_request<R: Request>(&self, request: &R) -> Result<reqwest::Request> {
+        format_request(&self.base_url, &self.token, request)
+    }
+
+    fn execute<T>(&self, endpoint: &str, req: reqwest::Request) -> Result<T>
+    where
+        T: for<'de> serde::Deserialize<'de>,
+    {
+        let cache = self.cache.as_ref();
+        if let Some(cache) = cache {
+            if let Some(msg) = cache.get_blocking(req.url())? {
+                return serde_json::from_str(&msg).map_err(|error| Error::from_body(error, msg));
+            }
+        }
+        let req = into_blocking(req);
+        let msg = self.fetch(endpoint, &req)?;
+        let res =
+            serde_json::from_str(&msg).map_err(|error| Error::from_body(error, msg.clone()))?;
+        if let Some(cache) = cache {
+            cache.put_blocking(req.url(), &msg);
+        }
+        Ok(res)
+    }
+
+    /// Send a request, retrying it according to the retry policy, and return the response body.
+    fn fetch(&self, endpoint: &str, req: &reqwest::blocking::Request) -> Result<String> {
+        let policy = &self.retry_policy;
+        let mut attempt = 0;
+        loop {
+            let attempt_req = req
+                .try_clone()
+                .expect("Requests with streaming bodies cannot be retried");
+            let delay = match self.http.execute(attempt_req) {
+                Ok(res) => {
+                    let status = res.status();
+                    if status.is_success() {
+                        return res.text().map_err(Error::transport);
+                    }
+                    match policy.retry_response(attempt, status, res.headers()) {
+                        Some(delay) => delay,
+                        None => {
+                            let msg = res.text().unwrap_or_default();
+                            return Err(Error::from_response(status, msg));
+                        }
+                    }
+                }
+                Err(e) => match policy.retry_error(attempt, &e) {
+                    Some(delay) => delay,
+                    None => return Err(Error::transport(e)),
+                },
+            };
+            warn!(endpoint, attempt, ?delay, "Retrying request");
+            attempt += 1;
+            std::thread::sleep(delay);
+        }
+    }
+
+    /// Send a single `Request`
+    pub fn send<R: Request>(&self, request: &R) -> Result<R::Response> {
+        let req = self.format_request(request)?;
+        self.execute(&request.endpoint(), req)
+    }
+
+    /// Send a paginated request, returning an iterator over the pages of the response. Iteration
+    /// stops after the first error. Use [`Pages::results`] to iterate over the results instead.
+    pub fn send_paginated<'a, R: PaginatedRequest>(&'a self, request: &'a R) -> Pages<'a, R> {
+        Pages {
+            client: self,
+            request,
+            paginator: request.paginator(),
+            state: State::Start(request.initial_page()),
+        }
+    }
+}
+
+/// Convert a formatted request for the blocking client. Formatted requests only have in-memory
+/// bodies, so the body can always be copied.
+fn into_blocking(req: reqwest::Request) -> reqwest::blocking::Request {
+    let mut blocking = reqwest::blocking::Request::new(req.method().clone(), req.url().clone());
+    *blocking.headers_mut() = req.headers().clone();
+    *blocking.body_mut() = req
+        .body()
+        .and_then(|body| body.as_bytes())
+        .map(|bytes| bytes.to_vec().into());
+    blocking
+}
+
+/// The pages of a paginated request, fetched as the iterator advances.
+pub struct Pages<'a, R: PaginatedRequest> {
+    client: &'a Client,
+    request: &'a R,
+    paginator: R::Paginator,
+    state: State<<R as PaginatedRequest>::Data>,
+}
+
+impl<R: PaginatedRequest> Pages<'_, R> {
+    fn fetch(&self, page: Option<&<R as PaginatedRequest>::Data>) -> Result<R::Response> {
+        let mut req = self.client.format_request(self.request)?;
+        if let Some(page) = page {
+            self.paginator
+                .modifier(page.clone())
+                .modify_request(&mut req)
+                .map_err(Error::from_vila)?;
+        }
+        self.client.execute(&self.request.endpoint(), req)
+    }
+}
+
+impl<'a, R> Pages<'a, R>
+where
+    R: PaginatedRequest,
+    R::Response: PageResults,
+{
+    /// Iterate over the results of every page, fetching pages as needed. Iteration stops after
+    /// the first error.
+    pub fn results(self) -> Results<'a, R> {
+        Results {
+            pages: self,
+            results: Vec::new().into_iter(),
+        }
+    }
+}
+
+impl<R: PaginatedRequest> Iterator for Pages<'_, R> {
+    type Item = Result<R::Response>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let page = match std::mem::replace(&mut self.state, State::End) {
+            State::Start(page) => page,
+            State::Next(page) => Some(page),
+            State::End => return None,
+        };
+        match self.fetch(page.as_ref()) {
+            Ok(response) => {
+                self.state = self.paginator.next(page.as_ref(), &response);
+                Some(Ok(response))
+            }
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+/// The results of a paginated request, see [`Pages::results`].
+pub struct Results<'a, R>
+where
+    R: PaginatedRequest,
+    R::Response: PageResults,
+{
+    pages: Pages<'a, R>,
+    results: std::vec::IntoIter<<R::Response as PageResults>::Item>,
+}
+
+impl<R> Iterator for Results<'_, R>
+where
+    R: PaginatedRequest,
+    R::Response: PageResults,
+{
+    type Item = Result<<R::Response as PageResults>::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(result) = self.results.next() {
+                return Some(Ok(result));
+            }
+            match self.pages.next()? {
+                Ok(page) => self.results = page.into_results().into_iter(),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// A page of a paginated response, which holds a list of results.
+pub trait PageResults {
+    type Item;
+
+    fn into_results(self) -> Vec<Self::Item>;
+}
+
+impl PageResults for AggregateWrapper {
+    type Item = super::Aggregate;
+
+    fn into_results(self) -> Vec<Self::Item> {
+        self.results
+    }
+}
+
+impl PageResults for QuoteWrapper {
+    type Item = super::Quote;
+
+    fn into_results(self) -> Vec<Self::Item> {
+        self.results
+    }
+}
+
+impl PageResults for TradeWrapper {
+    type Item = super::Trade;
+
+    fn into_results(self) -> Vec<Self::Item> {
+        self.results
+    }
+}
+
+impl<V> PageResults for IndicatorWrapper<V> {
+    type Item = V;
+
+    fn into_results(self) -> Vec<Self::Item> {
+        self.results.values
+    }
+}
+
+impl PageResults for ConditionsWrapper {
+    type Item = super::Condition;
+
+    fn into_results(self) -> Vec<Self::Item> {
+        self.results
+    }
+}
+
+impl PageResults for DividendsWrapper {
+    type Item = super::Dividend;
+
+    fn into_results(self) -> Vec<Self::Item> {
+        self.results
+    }
+}
+
+impl PageResults for FinancialsWrapper {
+    type Item = super::Financials;
+
+    fn into_results(self) -> Vec<Self::Item> {
+        self.results
+    }
+}
+
+impl PageResults for SplitsWrapper {
+    type Item = super::Split;
+
+    fn into_results(self) -> Vec<Self::Item> {
+        self.results
+    }
+}
+
+impl PageResults for TickerNewsWrapper {
+    type Item = super::NewsArticle;
+
+    fn into_results(self) -> Vec<Self::Item> {
+        self.results
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rest::{GetLastTrade, GetSplits};
+    use mockito::{mock, Matcher};
+
+    #[test]
+    fn iterates_over_pages() {
+        let _second = mock("GET", "/v3/reference/splits")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()),
+                Matcher::UrlEncoded("ticker".into(), "TSLA".into()),
+                Matcher::UrlEncoded("cursor".into(), "YXA9Mg".into()),
+            ]))
+            .with_body(r#"{"request_id":"b2","results":[{"execution_date":"2020-08-31","split_from":1,"split_to":5,"ticker":"TSLA"}],"status":"OK"}"#)
+            .create();
+        let _first = mock("GET", "/v3/reference/splits")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()),
+                Matcher::UrlEncoded("ticker".into(), "TSLA".into()),
+            ]))
+            .with_body(r#"{"next_url":"https://api.polygon.io/v3/reference/splits?cursor=YXA9Mg","request_id":"b1","results":[{"execution_date":"2022-08-25","split_from":1,"split_to":3,"ticker":"TSLA"}],"status":"OK"}"#)
+            .create();
+
+        let client = Client::builder("TOKEN")
+            .base_url(mockito::server_url())
+            .build();
+        let req = GetSplits::new().ticker("TSLA");
+        let pages: Vec<_> = client.send_paginated(&req).collect::<Result<_>>().unwrap();
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].request_id, "b1");
+        assert_eq!(pages[1].results[0].split_to, 5.into());
+    }
+
+    #[test]
+    fn iterates_over_results() {
+        let _first = mock("GET", "/v3/reference/splits")
+            .match_query(Matcher::Regex("^ticker=NFLX&limit=1000&apiKey=TOKEN$".into()))
+            .with_body(r#"{"next_url":"https://api.polygon.io/v3/reference/splits?cursor=YXA9Mw","request_id":"e1","results":[{"execution_date":"2015-07-15","split_from":1,"split_to":7,"ticker":"NFLX"}],"status":"OK"}"#)
+            .create();
+        let _second = mock("GET", "/v3/reference/splits")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("ticker".into(), "NFLX".into()),
+                Matcher::UrlEncoded("cursor".into(), "YXA9Mw".into()),
+            ]))
+            .with_body(r#"{"request_id":"e2","results":[{"execution_date":"2004-02-12","split_from":1,"split_to":2,"ticker":"NFLX"}],"status":"OK"}"#)
+            .create();
+
+        let client = Client::builder("TOKEN")
+            .base_url(mockito::server_url())
+            .build();
+        let req = GetSplits::new().ticker("NFLX");
+        let splits: Vec<_> = client
+            .send_paginated(&req)
+            .results()
+            .collect::<Result<_>>()
+            .unwrap();
+        let split_to: Vec<_> = splits.iter().map(|s| s.split_to).collect();
+        assert_eq!(split_to, vec![7.into(), 2.into()]);
+    }
+
+    #[test]
+    fn returns_typed_errors() {
+        let _m = mock("GET", "/v2/last/trade/BLOCK")
+            .match_query(Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()))
+            .with_status(404)
+            .with_body(r#"{"status":"NOT_FOUND","request_id":"c3","message":"Data not found."}"#)
+            .create();
+
+        let client = Client::builder("TOKEN")
+            .base_url(mockito::server_url())
+            .build();
+        let res = client.send(&GetLastTrade::new("BLOCK"));
+        assert!(matches!(res, Err(Error::NotFound { .. })));
+    }
+
+    #[test]
+    fn serves_cached_responses() {
+        let dir = std::env::temp_dir().join(format!("polygon-blocking-{}", std::process::id()));
+        let m = mock("GET", "/v3/reference/splits")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()),
+                Matcher::UrlEncoded("ticker".into(), "INTC".into()),
+            ]))
+            .with_body(r#"{"request_id":"d4","results":[{"execution_date":"2000-07-31","split_from":1,"split_to":2,"ticker":"INTC"}],"status":"OK"}"#)
+            .expect(1)
+            .create();
+
+        let client = Client::builder("TOKEN")
+            .base_url(mockito::server_url())
+            .cache(ResponseCache::new(&dir))
+            .build();
+        let req = GetSplits::new().ticker("INTC");
+        let first = client.send(&req).unwrap();
+        let second = client.send(&req).unwrap();
+        m.assert();
+        assert_eq!(first.results, second.results);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,380 @@
+use crate::errors::{Error, Result};
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use chrono_tz::US::Eastern;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tracing::warn;
+use url::Url;
+
+/// How long a cached response may be served for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Freshness {
+    /// Historical data for a range that has already closed, which never changes.
+    Immutable,
+    /// Split and dividend adjusted bars for a range that has already closed, which change when
+    /// the ticker splits or pays a dividend.
+    Adjusted,
+    /// Data for the current session, which changes as the session progresses.
+    Session,
+    /// Reference data, which changes rarely.
+    Reference,
+    /// Real-time data, which is never cached.
+    Uncached,
+}
+
+impl Freshness {
+    fn of(url: &Url, today: NaiveDate) -> Self {
+        let segments: Vec<&str> = url.path().trim_matches('/').split('/').collect();
+        let historical = |date: Option<NaiveDate>, closed: Freshness| match date {
+            Some(date) if date < today => closed,
+            _ => Freshness::Session,
+        };
+        // Polygon adjusts aggregates unless told otherwise
+        let unadjusted = url
+            .query_pairs()
+            .any(|(k, v)| (k == "unadjusted" && v == "true") || (k == "adjusted" && v == "false"));
+        match segments.as_slice() {
+            ["v2", "aggs", "ticker", _, "prev"] => Freshness::Session,
+            ["v2", "aggs", "ticker", _, "range", _, _, _, to] if unadjusted => {
+                historical(parse_date(to), Freshness::Immutable)
+            }
+            ["v2", "aggs", "ticker", _, "range", _, _, _, to] => {
+                historical(parse_date(to), Freshness::Adjusted)
+            }
+            ["v2", "ticks", "stocks", _, _, date] => {
+                historical(parse_date(date), Freshness::Immutable)
+            }
+            ["v2", "last", ..] | ["v2", "snapshot", ..] | ["v1", "marketstatus", "now"] => {
+                Freshness::Uncached
+            }
+            _ => Freshness::Reference,
+        }
+    }
+}
+
+/// Parse the end of a range, which Polygon accepts either as a date or as a timestamp in
+/// milliseconds.
+fn parse_date(s: &str) -> Option<NaiveDate> {
+    match s.parse::<i64>() {
+        Ok(ms) => Some(Eastern.timestamp_millis_opt(ms).single()?.date_naive()),
+        Err(_) => s.parse().ok(),
+    }
+}
+
+/// A stable 64-bit FNV-1a hash, used to name the cache files.
+fn fnv1a(s: &str) -> u64 {
+    s.bytes().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    url: String,
+    fetched_at: DateTime<Utc>,
+    body: String,
+}
+
+/// An on-disk cache of REST responses, keyed on the request URL without the API key.
+///
+/// Ticks and unadjusted aggregates for historical ranges that have already closed are cached
+/// forever. Adjusted aggregates, responses for the current session and reference data are cached
+/// for a configurable time, while real-time endpoints such as snapshots and last trades are never
+/// cached.
+///
+/// Caching is transparent to requests: any [`Request`](vila::Request) or
+/// [`PaginatedRequest`](vila::pagination::PaginatedRequest) sent through a
+/// [`ThrottledClient`](super::ThrottledClient) or the blocking client built with a cache is
+/// served from it. The plain [`client`](super::client) does not cache responses.
+#[derive(Clone, Debug)]
+pub struct ResponseCache {
+    dir: PathBuf,
+    session_ttl: Duration,
+    adjusted_ttl: Duration,
+    reference_ttl: Duration,
+    offline: bool,
+}
+
+impl ResponseCache {
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            session_ttl: Duration::from_secs(60),
+            adjusted_ttl: Duration::from_secs(24 * 60 * 60),
+            reference_ttl: Duration::from_secs(24 * 60 * 60),
+            offline: false,
+        }
+    }
+
+    pub fn session_ttl(mut self, ttl: Duration) -> Self {
+        self.session_ttl = ttl;
+        self
+    }
+
+    /// How long adjusted aggregates for closed ranges are cached, since past splits and
+    /// dividends change them. Defaults to a day.
+    pub fn adjusted_ttl(mut self, ttl: Duration) -> Self {
+        self.adjusted_ttl = ttl;
+        self
+    }
+
+    pub fn reference_ttl(mut self, ttl: Duration) -> Self {
+        self.reference_ttl = ttl;
+        self
+    }
+
+    /// Serve every request from the cache, regardless of how old the cached response is, and
+    /// fail with [`Error::CacheMiss`] instead of sending requests that are not cached.
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    fn key(url: &Url) -> Url {
+        let mut key = url.clone();
+        let pairs: Vec<(String, String)> = url
+            .query_pairs()
+            .filter(|(k, _)| k != "apiKey")
+            .map(|(k, v)| (k.into_owned(), v.into_owned()))
+            .collect();
+        if pairs.is_empty() {
+            key.set_query(None);
+        } else {
+            key.query_pairs_mut().clear().extend_pairs(pairs);
+        }
+        key
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{:016x}.json", fnv1a(key)))
+    }
+
+    fn is_fresh(&self, freshness: Freshness, fetched_at: DateTime<Utc>) -> bool {
+        let ttl = match freshness {
+            Freshness::Immutable => return true,
+            Freshness::Uncached => return false,
+            Freshness::Session => self.session_ttl,
+            Freshness::Adjusted => self.adjusted_ttl,
+            Freshness::Reference => self.reference_ttl,
+        };
+        (Utc::now() - fetched_at)
+            .to_std()
+            .map_or(true, |age| age < ttl)
+    }
+
+    /// The cache key of a request, and how long its response may be served for.
+    fn classify(url: &Url) -> (Url, Freshness) {
+        let key = Self::key(url);
+        let today = Utc::now().with_timezone(&Eastern).date_naive();
+        let freshness = Freshness::of(&key, today);
+        (key, freshness)
+    }
+
+    /// Decode a cache file, ignoring files that belong to a different key with the same hash.
+    fn decode(key: &Url, path: &Path, data: std::io::Result<Vec<u8>>) -> Option<CacheEntry> {
+        let data = match data {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+            Err(e) => {
+                warn!(path = %path.display(), error = %e, "Failed to read cached response");
+                return None;
+            }
+        };
+        serde_json::from_slice::<CacheEntry>(&data)
+            .ok()
+            .filter(|entry| entry.url == key.as_str())
+    }
+
+    /// Serve a request from its cached entry, if the entry may still be served.
+    fn serve(
+        &self,
+        key: &Url,
+        freshness: Freshness,
+        entry: Option<CacheEntry>,
+    ) -> Result<Option<String>> {
+        match entry {
+            Some(entry) if self.offline || self.is_fresh(freshness, entry.fetched_at) => {
+                Ok(Some(entry.body))
+            }
+            _ if self.offline => Err(Error::CacheMiss {
+                url: key.to_string(),
+            }),
+            _ => Ok(None),
+        }
+    }
+
+    /// The entry to store for a response, or `None` if it is never cached.
+    fn entry(url: &Url, body: &str) -> Option<CacheEntry> {
+        let (key, freshness) = Self::classify(url);
+        if freshness == Freshness::Uncached {
+            return None;
+        }
+        Some(CacheEntry {
+            url: key.to_string(),
+            fetched_at: Utc::now(),
+            body: body.to_string(),
+        })
+    }
+
+    /// A temporary file to write an entry through, so that readers never see partial entries.
+    /// Each write has its own temporary file, so concurrent writers of an entry don't clobber
+    /// each other.
+    fn temp_path(path: &Path) -> PathBuf {
+        static WRITES: AtomicU64 = AtomicU64::new(0);
+        path.with_extension(format!(
+            "{}-{}.tmp",
+            std::process::id(),
+            WRITES.fetch_add(1, Ordering::Relaxed)
+        ))
+    }
+
+    /// Look up the cached response for a request. Returns `Ok(None)` when the request should be
+    /// sent to Polygon.
+    pub(crate) async fn get(&self, url: &Url) -> Result<Option<String>> {
+        let (key, freshness) = Self::classify(url);
+        if freshness == Freshness::Uncached && !self.offline {
+            return Ok(None);
+        }
+        let path = self.path(key.as_str());
+        let entry = Self::decode(&key, &path, tokio::fs::read(&path).await);
+        self.serve(&key, freshness, entry)
+    }
+
+    /// Store the response for a request. Failing to write to the cache does not fail the
+    /// request, so errors are only logged.
+    pub(crate) async fn put(&self, url: &Url, body: &str) {
+        let entry = match Self::entry(url, body) {
+            Some(entry) => entry,
+            None => return,
+        };
+        let path = self.path(&entry.url);
+        let tmp = Self::temp_path(&path);
+        let written = async {
+            tokio::fs::create_dir_all(&self.dir).await?;
+            tokio::fs::write(&tmp, serde_json::to_vec(&entry)?).await?;
+            tokio::fs::rename(&tmp, &path).await
+        };
+        if let Err(e) = written.await {
+            warn!(path = %path.display(), error = %e, "Failed to cache response");
+        }
+    }
+
+    /// Like [`ResponseCache::get`], for the blocking client.
+    #[cfg(feature = "blocking")]
+    pub(crate) fn get_blocking(&self, url: &Url) -> Result<Option<String>> {
+        let (key, freshness) = Self::classify(url);
+        if freshness == Freshness::Uncached && !self.offline {
+            return Ok(None);
+        }
+        let path = self.path(key.as_str());
+        let entry = Self::decode(&key, &path, std::fs::read(&path));
+        self.serve(&key, freshness, entry)
+    }
+
+    /// Like [`ResponseCache::put`], for the blocking client.
+    #[cfg(feature = "blocking")]
+    pub(crate) fn put_blocking(&self, url: &Url, body: &str) {
+        let entry = match Self::entry(url, body) {
+            Some(entry) => entry,
+            None => return,
+        };
+        let path = self.path(&entry.url);
+        let tmp = Self::temp_path(&path);
+        let written = (|| {
+            std::fs::create_dir_all(&self.dir)?;
+            std::fs::write(&tmp, serde_json::to_vec(&entry)?)?;
+            std::fs::rename(&tmp, &path)
+        })();
+        if let Err(e) = written {
+            warn!(path = %path.display(), error = %e, "Failed to cache response");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rest::{GetSplits, ThrottledClient};
+    use mockito::{mock, Matcher};
+
+    #[test]
+    fn freshness() {
+        let today = NaiveDate::from_ymd_opt(2021, 11, 5).unwrap();
+        let of = |url: &str| Freshness::of(&Url::parse(url).unwrap(), today);
+        assert_eq!(
+            of("https://api.polygon.io/v2/aggs/ticker/AAPL/range/1/day/1614574800000/1614661199999?unadjusted=true"),
+            Freshness::Immutable
+        );
+        assert_eq!(
+            of("https://api.polygon.io/v2/aggs/ticker/AAPL/range/1/day/1614574800000/1614661199999?unadjusted=false"),
+            Freshness::Adjusted
+        );
+        assert_eq!(
+            of("https://api.polygon.io/v2/aggs/ticker/AAPL/range/1/day/2021-03-01/2021-03-02?adjusted=false"),
+            Freshness::Immutable
+        );
+        assert_eq!(
+            of("https://api.polygon.io/v2/aggs/ticker/AAPL/range/1/day/2021-11-01/2021-11-05"),
+            Freshness::Session
+        );
+        assert_eq!(
+            of("https://api.polygon.io/v2/ticks/stocks/nbbo/AAPL/2021-11-04"),
+            Freshness::Immutable
+        );
+        assert_eq!(
+            of("https://api.polygon.io/v3/reference/splits?ticker=AAPL"),
+            Freshness::Reference
+        );
+        assert_eq!(
+            of("https://api.polygon.io/v2/last/trade/AAPL"),
+            Freshness::Uncached
+        );
+    }
+
+    #[test]
+    fn key_excludes_api_key() {
+        let url = Url::parse("https://api.polygon.io/v3/reference/splits?ticker=AAPL&apiKey=TOKEN")
+            .unwrap();
+        assert_eq!(
+            ResponseCache::key(&url).as_str(),
+            "https://api.polygon.io/v3/reference/splits?ticker=AAPL"
+        );
+    }
+
+    #[tokio::test]
+    async fn serves_cached_responses() {
+        let dir = std::env::temp_dir().join(format!("polygon-cache-{}", std::process::id()));
+        let m = mock("GET", "/v3/reference/splits")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()),
+                Matcher::UrlEncoded("ticker".into(), "NVDA".into()),
+            ]))
+            .with_body(r#"{"request_id":"6ddd3a5e9f1b5f6d","results":[{"execution_date":"2021-07-20","split_from":1,"split_to":4,"ticker":"NVDA"}],"status":"OK"}"#)
+            .expect(1)
+            .create();
+
+        let client = ThrottledClient::builder("TOKEN")
+            .base_url(mockito::server_url())
+            .cache(ResponseCache::new(&dir))
+            .build()
+            .unwrap();
+        let req = GetSplits::new().ticker("NVDA");
+        let first = client.send(&req).await.unwrap();
+        let second = client.send(&req).await.unwrap();
+        m.assert();
+        assert_eq!(first.results, second.results);
+        assert_eq!(client.metrics().cache_hits, 1);
+
+        let offline = ThrottledClient::builder("TOKEN")
+            .base_url(mockito::server_url())
+            .cache(ResponseCache::new(&dir).offline(true))
+            .build()
+            .unwrap();
+        assert!(offline.send(&req).await.is_ok());
+        let res = offline.send(&GetSplits::new().ticker("AMD")).await;
+        assert!(matches!(res, Err(Error::CacheMiss { .. })));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,56 @@
+use std::collections::HashMap;
+use url::Url;
+use vila::pagination::query::*;
+
+/// Pagination data for endpoints that page through results with a `cursor` query parameter,
+/// which Polygon embeds in the `next_url` of each response.
+#[derive(Clone, Debug)]
+pub struct CursorPaginationData {
+    cursor: String,
+}
+
+impl From<CursorPaginationData> for QueryModifier {
+    fn from(d: CursorPaginationData) -> QueryModifier {
+        let mut data = HashMap::new();
+        data.insert("cursor".into(), d.cursor);
+        QueryModifier { data }
+    }
+}
+
+/// Responses that link to their next page.
+pub(crate) trait NextUrl {
+    fn next_url(&self) -> Option<&str>;
+}
+
+pub(crate) fn cursor_from_url(next_url: &str) -> Option<String> {
+    Url::parse(next_url)
+        .ok()?
+        .query_pairs()
+        .find(|(k, _)| k == "cursor")
+        .map(|(_, v)| v.into_owned())
+}
+
+pub(crate) fn cursor_paginator<T: NextUrl>() -> QueryPaginator<T, CursorPaginationData> {
+    QueryPaginator::new(|_: Option<&CursorPaginationData>, res: &T| {
+        res.next_url()
+            .and_then(cursor_from_url)
+            .map(|cursor| CursorPaginationData { cursor })
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_cursor() {
+        assert_eq!(
+            cursor_from_url("https://api.polygon.io/v3/reference/splits?cursor=YXA9JTdCJTIy%3D"),
+            Some("YXA9JTdCJTIy=".into())
+        );
+        assert_eq!(
+            cursor_from_url("https://api.polygon.io/v3/reference/splits?limit=10"),
+            None
+        );
+    }
+}
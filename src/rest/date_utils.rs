@@ -1,5 +1,9 @@
 use super::stocks::Timespan;
-use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, RoundingError};
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, LocalResult, NaiveDate, NaiveDateTime, Offset,
+    RoundingError, TimeZone, Utc,
+};
+use chrono_tz::Tz;
 
 const MAX_SECONDS_TIMESTAMP_FOR_NANOS: i64 = 9_223_372_036;
 
@@ -143,6 +147,65 @@ pub(crate) fn adjust_timeperiods(
     (from, to)
 }
 
+/// Which end of a range a local time bounds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Bound {
+    Start,
+    End,
+}
+
+/// Convert a local time in `tz` to UTC, so that a range covers exactly the instants whose local
+/// times are within it. A time that happens twice when clocks fall back resolves to its first
+/// occurrence at the start of a range, and its second at the end. A time skipped when clocks
+/// spring forward resolves to the transition itself, which is the first instant after it.
+pub(crate) fn resolve_local(tz: Tz, local: NaiveDateTime, bound: Bound) -> DateTime<Utc> {
+    match (tz.from_local_datetime(&local), bound) {
+        (LocalResult::Single(time), _) => time.with_timezone(&Utc),
+        (LocalResult::Ambiguous(earliest, _), Bound::Start) => earliest.with_timezone(&Utc),
+        (LocalResult::Ambiguous(_, latest), Bound::End) => latest.with_timezone(&Utc),
+        (LocalResult::None, bound) => {
+            let transition = gap_transition(tz, local);
+            match bound {
+                Bound::Start => transition,
+                Bound::End => transition - Duration::milliseconds(1),
+            }
+        }
+    }
+}
+
+/// The instant clocks sprang forward over the skipped local time `local`.
+fn gap_transition(tz: Tz, local: NaiveDateTime) -> DateTime<Utc> {
+    let offset_at = |time: NaiveDateTime| tz.offset_from_utc_datetime(&time).fix();
+    // Transitions are months apart, so the offsets a day either side of the gap are the offsets
+    // before and after it.
+    let before = offset_at(local - Duration::days(1));
+    let after = offset_at(local + Duration::days(1));
+    // Read with the offset after the transition the local time is before it, and read with the
+    // offset before the transition it is after it. Transitions happen on whole seconds.
+    let utc_seconds =
+        |offset: FixedOffset| local.and_utc().timestamp() - i64::from(offset.local_minus_utc());
+    let (mut lo, mut hi) = (utc_seconds(after), utc_seconds(before) + 1);
+    let at = |seconds: i64| DateTime::from_timestamp(seconds, 0).unwrap();
+    while hi - lo > 1 {
+        let mid = lo + (hi - lo) / 2;
+        if offset_at(at(mid).naive_utc()) == after {
+            hi = mid;
+        } else {
+            lo = mid;
+        }
+    }
+    at(hi)
+}
+
+/// The last millisecond of the bar of `multiplier` timespans that starts at `start`.
+pub(crate) fn bar_end(start: NaiveDateTime, multiplier: u32, timespan: Timespan) -> NaiveDateTime {
+    let mut end = snap_forward(start, timespan);
+    for _ in 1..multiplier {
+        end = snap_forward(end + Duration::milliseconds(1), timespan);
+    }
+    end
+}
+
 pub(crate) fn next_pagination_date(
     from: NaiveDateTime,
     to: NaiveDateTime,
@@ -177,7 +240,8 @@ pub(crate) fn next_pagination_date(
     if periods <= i64::from(max_periods) {
         to
     } else if max_periods == 0 {
-        panic!("Limit is too small to create a request")
+        // The limit is smaller than the days in a single period, so request one period at a time
+        snap_forward(from, timespan)
     } else {
         let diff = i64::from(max_periods) - 1;
         let snap_to = match timespan {
@@ -556,5 +620,60 @@ mod test {
             NaiveDate::from_ymd(2027, 12, 31).and_hms_milli(23, 59, 59, 999)
         );
         assert_eq!(next_pagination_date(from, to, 3660, 1, Timespan::Year), to);
+        assert_eq!(
+            next_pagination_date(from, to, 10, 1, Timespan::Month),
+            NaiveDate::from_ymd(2023, 1, 31).and_hms_milli(23, 59, 59, 999)
+        );
+    }
+
+    #[test]
+    fn resolve_dst_transitions() {
+        use chrono_tz::US::Eastern;
+        let utc = |h, m| NaiveDate::from_ymd(2021, 3, 14).and_hms(h, m, 0);
+        // 02:30 does not exist on the day clocks spring forward from 02:00 EST to 03:00 EDT
+        let gap = NaiveDate::from_ymd(2021, 3, 14).and_hms(2, 30, 0);
+        assert_eq!(
+            resolve_local(Eastern, gap, Bound::Start).naive_utc(),
+            utc(7, 0)
+        );
+        assert_eq!(
+            resolve_local(Eastern, gap, Bound::End).naive_utc(),
+            utc(7, 0) - Duration::milliseconds(1)
+        );
+
+        // 01:30 happens twice on the day clocks fall back from 02:00 EDT to 01:00 EST
+        let utc = |h, m| NaiveDate::from_ymd(2021, 11, 7).and_hms(h, m, 0);
+        let fold = NaiveDate::from_ymd(2021, 11, 7).and_hms(1, 30, 0);
+        assert_eq!(
+            resolve_local(Eastern, fold, Bound::Start).naive_utc(),
+            utc(5, 30)
+        );
+        assert_eq!(
+            resolve_local(Eastern, fold, Bound::End).naive_utc(),
+            utc(6, 30)
+        );
+
+        let noon = NaiveDate::from_ymd(2021, 11, 7).and_hms(12, 0, 0);
+        assert_eq!(
+            resolve_local(Eastern, noon, Bound::End).naive_utc(),
+            utc(17, 0)
+        );
+        assert_eq!(
+            resolve_local(chrono_tz::UTC, noon, Bound::Start).naive_utc(),
+            noon
+        );
+    }
+
+    #[test]
+    fn test_bar_end() {
+        let start = NaiveDate::from_ymd(2023, 1, 30).and_hms(0, 0, 0);
+        assert_eq!(
+            bar_end(start, 3, Timespan::Day),
+            NaiveDate::from_ymd(2023, 2, 1).and_hms_milli(23, 59, 59, 999)
+        );
+        assert_eq!(
+            bar_end(start, 2, Timespan::Month),
+            NaiveDate::from_ymd(2023, 2, 28).and_hms_milli(23, 59, 59, 999)
+        );
     }
 }
@@ -0,0 +1,378 @@
+use super::{
+    Aggregate, AggregateWrapper, GetAggregate, GetQuotes, Quote, QuoteWrapper, ThrottledClient,
+    Timespan,
+};
+use crate::common::Ticker;
+use crate::errors::{Error, Result};
+use chrono::{Datelike, NaiveDate, Weekday};
+use futures::prelude::*;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+/// The kind of data to download for each partition.
+#[derive(Clone, Copy, Debug)]
+pub enum DownloadKind {
+    Aggregates { multiplier: u32, timespan: Timespan },
+    Quotes,
+}
+
+impl DownloadKind {
+    fn dir_name(&self) -> String {
+        match self {
+            DownloadKind::Aggregates {
+                multiplier,
+                timespan,
+            } => format!("aggregates_{}{}", multiplier, timespan),
+            DownloadKind::Quotes => "quotes".into(),
+        }
+    }
+}
+
+/// The data for a single ticker on a single day.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Partition {
+    pub ticker: Ticker,
+    pub date: NaiveDate,
+}
+
+#[derive(Debug)]
+pub enum PartitionStatus {
+    Downloaded {
+        rows: usize,
+    },
+    /// The partition was downloaded by a previous run.
+    Skipped,
+    Failed(Error),
+}
+
+/// Reported once for every partition of a download, in the order that partitions finish.
+#[derive(Debug)]
+pub struct DownloadProgress {
+    pub partition: Partition,
+    pub status: PartitionStatus,
+    /// The number of partitions finished so far, including this one.
+    pub completed: usize,
+    pub total: usize,
+}
+
+/// The partitions that have been fully written, recorded in a `checkpoints` file with one
+/// `TICKER DATE` line per partition.
+struct Checkpoints {
+    path: PathBuf,
+    done: Mutex<HashSet<Partition>>,
+}
+
+impl Checkpoints {
+    async fn load(path: PathBuf) -> Result<Self> {
+        let done = match tokio::fs::read_to_string(&path).await {
+            Ok(s) => s
+                .lines()
+                .filter_map(|line| {
+                    let (ticker, date) = line.split_once(' ')?;
+                    Some(Partition {
+                        ticker: ticker.into(),
+                        date: date.parse().ok()?,
+                    })
+                })
+                .collect(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashSet::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            path,
+            done: Mutex::new(done),
+        })
+    }
+
+    async fn contains(&self, partition: &Partition) -> bool {
+        self.done.lock().await.contains(partition)
+    }
+
+    async fn record(&self, partition: Partition) -> Result<()> {
+        let mut done = self.done.lock().await;
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        let line = format!("{} {}\n", partition.ticker, partition.date);
+        file.write_all(line.as_bytes()).await?;
+        file.flush().await?;
+        done.insert(partition);
+        Ok(())
+    }
+}
+
+/// Downloads historical data for many tickers over a date range, one ticker/day partition at a
+/// time.
+///
+/// Each partition is written to `<dir>/<kind>/<ticker>/<date>.json` as a JSON array of results,
+/// and recorded in `<dir>/<kind>/checkpoints` once it is complete. Partitions that are already
+/// recorded are skipped, so an interrupted download can be resumed by running it again.
+/// Weekends and the given holidays are skipped, while other days without data produce empty
+/// partitions.
+pub struct Downloader {
+    client: ThrottledClient,
+    dir: PathBuf,
+    concurrency: usize,
+    holidays: HashSet<NaiveDate>,
+}
+
+impl Downloader {
+    pub fn new<P: AsRef<Path>>(client: ThrottledClient, dir: P) -> Self {
+        Self {
+            client,
+            dir: dir.as_ref().to_path_buf(),
+            concurrency: 4,
+            holidays: HashSet::new(),
+        }
+    }
+
+    /// The number of partitions downloaded at once. Requests are still subject to the rate limit
+    /// of the client.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Days on which the market is closed besides weekends, such as the closed days returned by
+    /// [`GetMarketHolidays`](super::GetMarketHolidays).
+    pub fn holidays<I: IntoIterator<Item = NaiveDate>>(mut self, holidays: I) -> Self {
+        self.holidays.extend(holidays);
+        self
+    }
+
+    /// The file of a partition, in a directory named by [`Ticker::file_name`].
+    pub fn partition_path(&self, kind: DownloadKind, partition: &Partition) -> PathBuf {
+        self.dir
+            .join(kind.dir_name())
+            .join(partition.ticker.file_name())
+            .join(format!("{}.json", partition.date))
+    }
+
+    /// Download every partition for the tickers between `from` and `to`, both inclusive.
+    pub async fn download<T: Into<Ticker> + Clone>(
+        &self,
+        tickers: &[T],
+        from: NaiveDate,
+        to: NaiveDate,
+        kind: DownloadKind,
+    ) -> Result<impl Stream<Item = DownloadProgress> + '_> {
+        let kind_dir = self.dir.join(kind.dir_name());
+        tokio::fs::create_dir_all(&kind_dir).await?;
+        let checkpoints = Arc::new(Checkpoints::load(kind_dir.join("checkpoints")).await?);
+        let partitions: Vec<Partition> = tickers
+            .iter()
+            .cloned()
+            .map(Into::<Ticker>::into)
+            .flat_map(|ticker| {
+                from.iter_days()
+                    .take_while(move |date| *date <= to)
+                    .filter(|date| !matches!(date.weekday(), Weekday::Sat | Weekday::Sun))
+                    .filter(|date| !self.holidays.contains(date))
+                    .map(move |date| Partition {
+                        ticker: ticker.clone(),
+                        date,
+                    })
+            })
+            .collect();
+        let total = partitions.len();
+
+        Ok(stream::iter(partitions)
+            .map(move |partition| {
+                let checkpoints = checkpoints.clone();
+                async move {
+                    let status = if checkpoints.contains(&partition).await {
+                        PartitionStatus::Skipped
+                    } else {
+                        match self
+                            .download_partition(kind, &partition, &checkpoints)
+                            .await
+                        {
+                            Ok(rows) => PartitionStatus::Downloaded { rows },
+                            Err(e) => PartitionStatus::Failed(e),
+                        }
+                    };
+                    (partition, status)
+                }
+            })
+            .buffer_unordered(self.concurrency)
+            .enumerate()
+            .map(move |(i, (partition, status))| DownloadProgress {
+                partition,
+                status,
+                completed: i + 1,
+                total,
+            }))
+    }
+
+    async fn download_partition(
+        &self,
+        kind: DownloadKind,
+        partition: &Partition,
+        checkpoints: &Checkpoints,
+    ) -> Result<usize> {
+        let date = partition.date;
+        let path = self.partition_path(kind, partition);
+        let rows = match kind {
+            DownloadKind::Aggregates {
+                multiplier,
+                timespan,
+            } => {
+                let req = GetAggregate::new(
+                    &partition.ticker,
+                    date.and_hms_opt(0, 0, 0).unwrap(),
+                    date.and_hms_opt(23, 59, 59).unwrap(),
+                )
+                .multiplier(multiplier)
+                .timespan(timespan);
+                let pages: Vec<AggregateWrapper> =
+                    self.client.send_paginated(&req).try_collect().await?;
+                let results: Vec<Aggregate> = pages.into_iter().flat_map(|p| p.results).collect();
+                write_partition(&path, &results).await?
+            }
+            DownloadKind::Quotes => {
+                let req = GetQuotes::new(&partition.ticker, date);
+                let pages: Vec<QuoteWrapper> =
+                    self.client.send_paginated(&req).try_collect().await?;
+                let results: Vec<Quote> = pages.into_iter().flat_map(|p| p.results).collect();
+                write_partition(&path, &results).await?
+            }
+        };
+        checkpoints.record(partition.clone()).await?;
+        Ok(rows)
+    }
+}
+
+/// Write the results through a temporary file, so that partitions are never partially written.
+async fn write_partition<T: Serialize>(path: &Path, results: &[T]) -> Result<usize> {
+    let tmp = path.with_extension("tmp");
+    let data = serde_json::to_vec(results).map_err(|error| Error::Serde {
+        error,
+        msg: format!("Failed to serialize {}", path.display()),
+    })?;
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(&tmp, data).await?;
+    tokio::fs::rename(&tmp, path).await?;
+    Ok(results.len())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mockito::{mock, Matcher};
+
+    #[tokio::test]
+    async fn resumes_from_checkpoints() {
+        let dir = std::env::temp_dir().join(format!("polygon-download-{}", std::process::id()));
+        let m = mock(
+            "GET",
+            Matcher::Regex(r"^/v2/aggs/ticker/(IBM|F)/range/1/day/".into()),
+        )
+        .match_query(Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()))
+        .with_body(r#"{"ticker":"IBM","status":"OK","queryCount":1,"resultsCount":1,"adjusted":true,"results":[{"v":4507315,"vw":118.5154,"o":118.25,"c":118.84,"h":119.12,"l":117.5,"t":1614574800000,"n":41235}],"request_id":"6a7e466379af0a71039d60cc78e72282"}"#)
+        .expect(2)
+        .create();
+
+        let client = ThrottledClient::builder("TOKEN")
+            .base_url(mockito::server_url())
+            .build()
+            .unwrap();
+        let downloader = Downloader::new(client, &dir);
+        let kind = DownloadKind::Aggregates {
+            multiplier: 1,
+            timespan: Timespan::Day,
+        };
+        // 2021-02-27 and 2021-02-28 are a weekend
+        let from = NaiveDate::from_ymd_opt(2021, 2, 27).unwrap();
+        let to = NaiveDate::from_ymd_opt(2021, 3, 1).unwrap();
+
+        let progress: Vec<_> = downloader
+            .download(&["IBM", "F"], from, to, kind)
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert_eq!(progress.len(), 2);
+        assert_eq!(progress[1].completed, 2);
+        assert!(progress
+            .iter()
+            .all(|p| matches!(p.status, PartitionStatus::Downloaded { rows: 1 })));
+        let partition = Partition {
+            ticker: "IBM".into(),
+            date: to,
+        };
+        assert!(downloader.partition_path(kind, &partition).exists());
+
+        let progress: Vec<_> = downloader
+            .download(&["IBM", "F"], from, to, kind)
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert!(progress
+            .iter()
+            .all(|p| matches!(p.status, PartitionStatus::Skipped)));
+        m.assert();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn skips_holidays() {
+        let dir = std::env::temp_dir().join(format!("polygon-holidays-{}", std::process::id()));
+        let client = ThrottledClient::builder("TOKEN")
+            .base_url(mockito::server_url())
+            .build()
+            .unwrap();
+        // Presidents' Day follows a weekend, so there is nothing to download
+        let presidents_day = NaiveDate::from_ymd_opt(2021, 2, 15).unwrap();
+        let downloader = Downloader::new(client, &dir).holidays(vec![presidents_day]);
+        let from = NaiveDate::from_ymd_opt(2021, 2, 13).unwrap();
+        let progress: Vec<_> = downloader
+            .download(&["X:BTCUSD"], from, presidents_day, DownloadKind::Quotes)
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert!(progress.is_empty());
+
+        let partition = Partition {
+            ticker: "X:BTCUSD".into(),
+            date: presidents_day,
+        };
+        let path = downloader.partition_path(DownloadKind::Quotes, &partition);
+        assert!(path.ends_with("quotes/X_BTCUSD/2021-02-15.json"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn downloads_with_zero_concurrency() {
+        let dir = std::env::temp_dir().join(format!("polygon-concurrency-{}", std::process::id()));
+        let kind_dir = dir.join(DownloadKind::Quotes.dir_name());
+        std::fs::create_dir_all(&kind_dir).unwrap();
+        std::fs::write(kind_dir.join("checkpoints"), "MSFT 2021-03-01\n").unwrap();
+        let client = ThrottledClient::builder("TOKEN")
+            .base_url(mockito::server_url())
+            .build()
+            .unwrap();
+        let downloader = Downloader::new(client, &dir).concurrency(0);
+        let date = NaiveDate::from_ymd_opt(2021, 3, 1).unwrap();
+        let progress = downloader
+            .download(&["MSFT"], date, date, DownloadKind::Quotes)
+            .await
+            .unwrap()
+            .collect::<Vec<_>>();
+        let progress = tokio::time::timeout(std::time::Duration::from_secs(5), progress)
+            .await
+            .unwrap();
+        assert_eq!(progress.len(), 1);
+        assert!(matches!(progress[0].status, PartitionStatus::Skipped));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,216 @@
+//! Request formatting and the retry policy shared by the async and blocking clients.
+
+use crate::errors::{Error, Result};
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE, RETRY_AFTER};
+use reqwest::{StatusCode, Url};
+use std::time::Duration;
+use vila::{Request, RequestData};
+
+/// How failed requests are retried. Requests are retried when Polygon responds with a 429 or a
+/// 5xx status, or when the connection fails or times out.
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    /// The delay before the first retry, which is doubled on every subsequent retry. A
+    /// `Retry-After` header sent by Polygon takes precedence.
+    pub initial_backoff: Duration,
+    /// The longest delay before a retry, including delays requested by `Retry-After`.
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Default::default()
+        }
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .checked_mul(2u32.saturating_pow(attempt))
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff)
+    }
+
+    /// The delay before retrying a failed response, or `None` if it shouldn't be retried.
+    pub(super) fn retry_response(
+        &self,
+        attempt: u32,
+        status: StatusCode,
+        headers: &HeaderMap,
+    ) -> Option<Duration> {
+        let retryable = status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error();
+        if !retryable || attempt >= self.max_retries {
+            return None;
+        }
+        let delay = match retry_after(headers) {
+            Some(delay) => delay.min(self.max_backoff),
+            None => self.backoff(attempt),
+        };
+        Some(delay)
+    }
+
+    /// The delay before retrying a request that failed to connect or timed out, or `None` if it
+    /// shouldn't be retried.
+    pub(super) fn retry_error(&self, attempt: u32, error: &reqwest::Error) -> Option<Duration> {
+        if !(error.is_connect() || error.is_timeout()) || attempt >= self.max_retries {
+            return None;
+        }
+        Some(self.backoff(attempt))
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+/// The delay requested by a `Retry-After` header, given either in seconds or as an HTTP date.
+/// Dates in the past request no delay.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&Utc) - Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+/// Format a request to `base_url`, authenticated with the API key. The blocking client converts
+/// the formatted request, so that both clients apply paginators to the same request type.
+pub(super) fn format_request<R: Request>(
+    base_url: &str,
+    token: &str,
+    request: &R,
+) -> Result<reqwest::Request> {
+    let endpoint = request.endpoint();
+    let url = format!("{}/{}", base_url, endpoint.trim_matches('/'));
+    let mut url = Url::parse(&url).map_err(|e| Error::InvalidRequest(format!("{}: {}", url, e)))?;
+    let mut body = None;
+    let mut headers = request.headers();
+    match request.data() {
+        RequestData::Empty => {}
+        RequestData::Query(value) => {
+            serialize_query(&mut url, value)?;
+        }
+        RequestData::Form(value) => {
+            let form = serde_urlencoded::to_string(value).map_err(|e| invalid_data(&e))?;
+            headers.insert(
+                CONTENT_TYPE,
+                HeaderValue::from_static("application/x-www-form-urlencoded"),
+            );
+            body = Some(form.into_bytes());
+        }
+        RequestData::Json(value) => {
+            let json = serde_json::to_vec(value).map_err(|error| Error::Serde {
+                error,
+                msg: "Failed to serialize the request body".into(),
+            })?;
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+            body = Some(json);
+        }
+    }
+    serialize_query(&mut url, &[("apiKey", token)])?;
+    let mut req = reqwest::Request::new(R::METHOD, url);
+    *req.headers_mut() = headers;
+    *req.body_mut() = body.map(Into::into);
+    Ok(req)
+}
+
+fn serialize_query<T: serde::Serialize + ?Sized>(url: &mut Url, value: &T) -> Result<()> {
+    {
+        let mut pairs = url.query_pairs_mut();
+        value
+            .serialize(serde_urlencoded::Serializer::new(&mut pairs))
+            .map_err(|e| invalid_data(&e))?;
+    }
+    // Like reqwest, leave no trailing `?` when the value has no fields
+    if url.query() == Some("") {
+        url.set_query(None);
+    }
+    Ok(())
+}
+
+fn invalid_data(e: &serde_urlencoded::ser::Error) -> Error {
+    Error::InvalidRequest(format!("Failed to serialize the request data: {}", e))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rest::GetTickerNews;
+
+    #[test]
+    fn backoff() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff(0), Duration::from_millis(500));
+        assert_eq!(policy.backoff(2), Duration::from_secs(2));
+        assert_eq!(policy.backoff(40), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn retry_after_seconds_or_date() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("120"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(120)));
+
+        let later = (Utc::now() + chrono::Duration::seconds(90))
+            .format("%a, %d %b %Y %H:%M:%S GMT")
+            .to_string();
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(&later).unwrap());
+        let delay = retry_after(&headers).unwrap();
+        assert!(delay > Duration::from_secs(80) && delay <= Duration::from_secs(90));
+
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("soon"));
+        assert_eq!(retry_after(&headers), None);
+    }
+
+    #[test]
+    fn caps_retry_after() {
+        let policy = RetryPolicy::default();
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("86400"));
+        let delay = policy.retry_response(0, StatusCode::TOO_MANY_REQUESTS, &headers);
+        assert_eq!(delay, Some(Duration::from_secs(30)));
+
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Fri, 31 Dec 9999 23:59:59 GMT"),
+        );
+        let delay = policy.retry_response(0, StatusCode::SERVICE_UNAVAILABLE, &headers);
+        assert_eq!(delay, Some(Duration::from_secs(30)));
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("2"));
+        let delay = policy.retry_response(0, StatusCode::TOO_MANY_REQUESTS, &headers);
+        assert_eq!(delay, Some(Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn formats_query_and_token() {
+        let req = GetTickerNews::new().ticker("TSLA").limit(5);
+        let req = format_request("https://api.polygon.io", "TOKEN", &req).unwrap();
+        let url = req.url();
+        assert_eq!(url.path(), "/v2/reference/news");
+        let pairs: Vec<_> = url.query_pairs().collect();
+        assert!(pairs.contains(&("ticker".into(), "TSLA".into())));
+        assert!(pairs.contains(&("limit".into(), "5".into())));
+        assert_eq!(pairs.last().unwrap(), &("apiKey".into(), "TOKEN".into()));
+    }
+}
@@ -0,0 +1,342 @@
+use super::cursor::*;
+use super::{Aggregate, SortOrder, Timespan};
+use crate::common::Ticker;
+use chrono::{
+    serde::{ts_milliseconds, ts_milliseconds_option},
+    DateTime, Utc,
+};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::marker::PhantomData;
+use vila::pagination::{query::*, *};
+use vila::{Request, RequestData};
+
+/// A technical indicator that Polygon can compute server-side.
+pub trait IndicatorKind {
+    /// The name of the indicator in the endpoint path.
+    const NAME: &'static str;
+    /// A single value of the indicator.
+    type Value: for<'de> Deserialize<'de> + Unpin + 'static;
+}
+
+/// Indicators that are parameterized by a single window size.
+pub trait WindowedIndicator: IndicatorKind {}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SmaKind;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmaKind;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RsiKind;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MacdKind;
+
+impl IndicatorKind for SmaKind {
+    const NAME: &'static str = "sma";
+    type Value = IndicatorValue;
+}
+
+impl IndicatorKind for EmaKind {
+    const NAME: &'static str = "ema";
+    type Value = IndicatorValue;
+}
+
+impl IndicatorKind for RsiKind {
+    const NAME: &'static str = "rsi";
+    type Value = IndicatorValue;
+}
+
+impl IndicatorKind for MacdKind {
+    const NAME: &'static str = "macd";
+    type Value = MacdValue;
+}
+
+impl WindowedIndicator for SmaKind {}
+impl WindowedIndicator for EmaKind {}
+impl WindowedIndicator for RsiKind {}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct IndicatorValue {
+    #[serde(with = "ts_milliseconds")]
+    pub timestamp: DateTime<Utc>,
+    pub value: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MacdValue {
+    #[serde(with = "ts_milliseconds")]
+    pub timestamp: DateTime<Utc>,
+    pub value: Decimal,
+    pub signal: Decimal,
+    pub histogram: Decimal,
+}
+
+/// The aggregate bars the indicator was computed from.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Underlying {
+    #[serde(default)]
+    pub aggregates: Vec<Aggregate>,
+    pub url: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(bound(deserialize = "V: Deserialize<'de>"))]
+pub struct IndicatorResults<V> {
+    pub underlying: Option<Underlying>,
+    #[serde(default)]
+    pub values: Vec<V>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(bound(deserialize = "V: Deserialize<'de>"))]
+pub struct IndicatorWrapper<V> {
+    pub results: IndicatorResults<V>,
+    pub status: String,
+    pub request_id: String,
+    pub next_url: Option<String>,
+}
+
+impl<V> NextUrl for IndicatorWrapper<V> {
+    fn next_url(&self) -> Option<&str> {
+        self.next_url.as_deref()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SeriesType {
+    Open,
+    High,
+    Low,
+    Close,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IndicatorQuery {
+    #[serde(default, with = "ts_milliseconds_option")]
+    timestamp: Option<DateTime<Utc>>,
+    #[serde(rename = "timestamp.gte", default, with = "ts_milliseconds_option")]
+    timestamp_gte: Option<DateTime<Utc>>,
+    #[serde(rename = "timestamp.lte", default, with = "ts_milliseconds_option")]
+    timestamp_lte: Option<DateTime<Utc>>,
+    timespan: Timespan,
+    adjusted: bool,
+    window: Option<u32>,
+    short_window: Option<u32>,
+    long_window: Option<u32>,
+    signal_window: Option<u32>,
+    series_type: SeriesType,
+    expand_underlying: bool,
+    order: SortOrder,
+    limit: u32,
+}
+
+/// Request a technical indicator computed by Polygon over aggregate bars. Use the [`GetSma`],
+/// [`GetEma`], [`GetRsi`] and [`GetMacd`] aliases to construct requests.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GetIndicator<K> {
+    ticker: Ticker,
+    query: IndicatorQuery,
+    #[serde(skip)]
+    kind: PhantomData<K>,
+}
+
+pub type GetSma = GetIndicator<SmaKind>;
+pub type GetEma = GetIndicator<EmaKind>;
+pub type GetRsi = GetIndicator<RsiKind>;
+pub type GetMacd = GetIndicator<MacdKind>;
+
+impl<K: IndicatorKind> GetIndicator<K> {
+    pub fn new<T: Into<Ticker>>(ticker: T) -> Self {
+        Self {
+            ticker: ticker.into(),
+            query: IndicatorQuery {
+                timestamp: None,
+                timestamp_gte: None,
+                timestamp_lte: None,
+                timespan: Timespan::Day,
+                adjusted: true,
+                window: None,
+                short_window: None,
+                long_window: None,
+                signal_window: None,
+                series_type: SeriesType::Close,
+                expand_underlying: false,
+                order: SortOrder::Desc,
+                limit: 5000,
+            },
+            kind: PhantomData,
+        }
+    }
+
+    pub fn timestamp(mut self, timestamp: DateTime<Utc>) -> Self {
+        self.query.timestamp = Some(timestamp);
+        self
+    }
+
+    pub fn timestamp_gte(mut self, timestamp: DateTime<Utc>) -> Self {
+        self.query.timestamp_gte = Some(timestamp);
+        self
+    }
+
+    pub fn timestamp_lte(mut self, timestamp: DateTime<Utc>) -> Self {
+        self.query.timestamp_lte = Some(timestamp);
+        self
+    }
+
+    pub fn timespan(mut self, timespan: Timespan) -> Self {
+        self.query.timespan = timespan;
+        self
+    }
+
+    pub fn adjusted(mut self, adjusted: bool) -> Self {
+        self.query.adjusted = adjusted;
+        self
+    }
+
+    pub fn series_type(mut self, series_type: SeriesType) -> Self {
+        self.query.series_type = series_type;
+        self
+    }
+
+    /// Include the aggregate bars used to compute the indicator in the response.
+    pub fn expand_underlying(mut self, expand_underlying: bool) -> Self {
+        self.query.expand_underlying = expand_underlying;
+        self
+    }
+
+    pub fn order(mut self, order: SortOrder) -> Self {
+        self.query.order = order;
+        self
+    }
+
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.query.limit = limit;
+        self
+    }
+}
+
+impl<K: WindowedIndicator> GetIndicator<K> {
+    pub fn window(mut self, window: u32) -> Self {
+        self.query.window = Some(window);
+        self
+    }
+}
+
+impl GetIndicator<MacdKind> {
+    pub fn short_window(mut self, window: u32) -> Self {
+        self.query.short_window = Some(window);
+        self
+    }
+
+    pub fn long_window(mut self, window: u32) -> Self {
+        self.query.long_window = Some(window);
+        self
+    }
+
+    pub fn signal_window(mut self, window: u32) -> Self {
+        self.query.signal_window = Some(window);
+        self
+    }
+}
+
+impl<K: IndicatorKind> Request for GetIndicator<K> {
+    type Data = IndicatorQuery;
+    type Response = IndicatorWrapper<K::Value>;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        format!("/v1/indicators/{}/{}", K::NAME, self.ticker).into()
+    }
+
+    fn data(&self) -> RequestData<&IndicatorQuery> {
+        RequestData::Query(&self.query)
+    }
+}
+
+impl<K: IndicatorKind> PaginatedRequest for GetIndicator<K> {
+    type Data = CursorPaginationData;
+    type Paginator = QueryPaginator<IndicatorWrapper<K::Value>, CursorPaginationData>;
+
+    fn paginator(&self) -> Self::Paginator {
+        cursor_paginator()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rest::client_with_url;
+    use chrono::TimeZone;
+    use futures::TryStreamExt;
+    use mockito::{mock, Matcher};
+    use rust_decimal_macros::dec;
+
+    #[tokio::test]
+    async fn get_sma() {
+        let url = mockito::server_url();
+        let _first = mock("GET", "/v1/indicators/sma/AAPL")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()),
+                Matcher::UrlEncoded("timespan".into(), "hour".into()),
+                Matcher::UrlEncoded("window".into(), "50".into()),
+                Matcher::UrlEncoded("series_type".into(), "close".into()),
+                Matcher::UrlEncoded("expand_underlying".into(), "true".into()),
+                Matcher::UrlEncoded("timestamp.gte".into(), "1664164800000".into()),
+            ]))
+            .with_body(format!(r#"{{"next_url":"{}/v1/indicators/sma/AAPL?cursor=YWRqdXN0ZWQ9dHJ1ZQ","request_id":"a47d1beb8c11b6ae897ab76cdbbf35a3","results":{{"underlying":{{"aggregates":[{{"c":75.0875,"h":75.15,"l":73.7975,"n":1,"o":74.06,"t":1577941200000,"v":135647456,"vw":74.6099}}],"url":"https://api.polygon.io/v2/aggs/ticker/AAPL/range/1/hour/1063281600000/1665180000000"}},"values":[{{"timestamp":1517562000016,"value":140.139}}]}},"status":"OK"}}"#, url))
+            .create();
+        let _second = mock("GET", "/v1/indicators/sma/AAPL")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()),
+                Matcher::UrlEncoded("cursor".into(), "YWRqdXN0ZWQ9dHJ1ZQ".into()),
+            ]))
+            .with_body(r#"{"request_id":"a47d1beb8c11b6ae897ab76cdbbf35a4","results":{"values":[{"timestamp":1517558400016,"value":139.98}]},"status":"OK"}"#)
+            .create();
+
+        let client = client_with_url(&url, "TOKEN");
+        let req = GetSma::new("AAPL")
+            .timespan(Timespan::Hour)
+            .window(50)
+            .expand_underlying(true)
+            .timestamp_gte(Utc.with_ymd_and_hms(2022, 9, 26, 4, 0, 0).unwrap());
+        let pages: Vec<_> = client.send_paginated(&req).try_collect().await.unwrap();
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].results.values[0].value, dec!(140.139));
+        assert_eq!(
+            pages[0].results.underlying.as_ref().unwrap().aggregates[0].c,
+            dec!(75.0875)
+        );
+        assert!(pages[1].results.underlying.is_none());
+
+        let json = serde_json::to_string(&req).unwrap();
+        let restored: GetSma = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&restored).unwrap(), json);
+    }
+
+    #[tokio::test]
+    async fn get_macd() {
+        let _m = mock("GET", "/v1/indicators/macd/AAPL")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()),
+                Matcher::UrlEncoded("short_window".into(), "12".into()),
+                Matcher::UrlEncoded("long_window".into(), "26".into()),
+                Matcher::UrlEncoded("signal_window".into(), "9".into()),
+            ]))
+            .with_body(r#"{"request_id":"604e7c8e4f5d6a2b3c1d0e9f8a7b6c5d","results":{"values":[{"histogram":38.3801666667,"signal":106.9811666667,"timestamp":1517562000016,"value":145.3613333333}]},"status":"OK"}"#)
+            .create();
+        let url = mockito::server_url();
+
+        let client = client_with_url(&url, "TOKEN");
+        let req = GetMacd::new("AAPL")
+            .short_window(12)
+            .long_window(26)
+            .signal_window(9);
+        let macd = client.send(&req).await.unwrap();
+        assert_eq!(macd.results.values[0].signal, dec!(106.9811666667));
+        assert_eq!(macd.results.values[0].histogram, dec!(38.3801666667));
+    }
+}
@@ -0,0 +1,355 @@
+use super::{
+    GetMarketHolidays, GetMarketStatus, MarketHoliday, MarketHolidayStatus, MarketStatus, Status,
+    ThrottledClient,
+};
+use crate::errors::Result;
+use chrono::{DateTime, NaiveDate, Timelike, Utc};
+use chrono_tz::US::Eastern;
+use futures::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::Duration;
+use tokio::time::sleep;
+
+/// The trading session of the US stock market.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum MarketSession {
+    Closed,
+    PreMarket,
+    Regular,
+    AfterHours,
+}
+
+impl MarketSession {
+    /// The session of a market status. Extended hours before noon in New York are pre-market,
+    /// and after-hours otherwise.
+    pub fn from_status(status: &MarketStatus) -> Self {
+        match status.market {
+            Status::Open => MarketSession::Regular,
+            Status::Closed => MarketSession::Closed,
+            Status::ExtendedHours if status.server_time.with_timezone(&Eastern).hour() < 12 => {
+                MarketSession::PreMarket
+            }
+            Status::ExtendedHours => MarketSession::AfterHours,
+        }
+    }
+
+    /// The session that follows this one, and the transition into it.
+    fn next(self) -> (Self, MarketTransition) {
+        match self {
+            MarketSession::Closed => (MarketSession::PreMarket, MarketTransition::PreMarketOpen),
+            MarketSession::PreMarket => (MarketSession::Regular, MarketTransition::RegularOpen),
+            MarketSession::Regular => (MarketSession::AfterHours, MarketTransition::Close),
+            MarketSession::AfterHours => (MarketSession::Closed, MarketTransition::AfterHoursEnd),
+        }
+    }
+
+    /// The transitions from this session to `to` in the order they happen within a trading day.
+    fn transitions(self, to: Self) -> Vec<MarketTransition> {
+        let mut session = self;
+        let mut transitions = Vec::new();
+        while session != to {
+            let (next, transition) = session.next();
+            transitions.push(transition);
+            session = next;
+        }
+        transitions
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum MarketTransition {
+    PreMarketOpen,
+    RegularOpen,
+    Close,
+    /// The regular session closed early, e.g. on the day after Thanksgiving.
+    EarlyClose,
+    AfterHoursEnd,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MarketEvent {
+    pub transition: MarketTransition,
+    /// The server time of the status in which the transition was first seen.
+    pub time: DateTime<Utc>,
+    pub status: MarketStatus,
+    /// The holiday on the day of the transition, if any.
+    pub holiday: Option<MarketHoliday>,
+}
+
+/// Watch the market status for transitions between trading sessions.
+///
+/// The market status is polled every `interval`, and the upcoming holidays are fetched once per
+/// trading day to tell early closes apart from regular closes. The first poll only sets the
+/// current session, which can be found with [`MarketSession::from_status`]. Sessions that are
+/// skipped between two polls still produce their transitions, in order, including when the polls
+/// fall on different trading days.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchMarket {
+    interval: Duration,
+}
+
+impl Default for WatchMarket {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(60),
+        }
+    }
+}
+
+impl WatchMarket {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The time between polls. Defaults to a minute.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+}
+
+#[derive(Default)]
+struct MarketWatcher {
+    /// The trading day of the last status, and its session.
+    session: Option<(NaiveDate, MarketSession)>,
+    holidays: Vec<MarketHoliday>,
+    holidays_date: Option<NaiveDate>,
+    events: VecDeque<MarketEvent>,
+}
+
+impl MarketWatcher {
+    /// The holiday on `date`, preferring an early close, and whether the market closes early.
+    fn holiday(&self, date: NaiveDate) -> (Option<MarketHoliday>, bool) {
+        let mut holidays = self.holidays.iter().filter(|h| h.date == date);
+        let early_close = holidays
+            .clone()
+            .find(|h| matches!(h.status, MarketHolidayStatus::EarlyClose { .. }));
+        let holiday = early_close.or_else(|| holidays.next()).cloned();
+        (holiday, early_close.is_some())
+    }
+
+    fn observe(&mut self, status: MarketStatus) {
+        let today = status.server_time.with_timezone(&Eastern).date_naive();
+        let session = MarketSession::from_status(&status);
+        if let Some((date, mut previous)) = self.session {
+            // The previous trading day is finished before today's sessions start
+            let mut transitions = Vec::new();
+            if date != today {
+                let finished = previous.transitions(MarketSession::Closed);
+                transitions.extend(finished.into_iter().map(|t| (date, t)));
+                previous = MarketSession::Closed;
+            }
+            let started = previous.transitions(session);
+            transitions.extend(started.into_iter().map(|t| (today, t)));
+            for (day, transition) in transitions {
+                let (holiday, early_close) = self.holiday(day);
+                let transition = match transition {
+                    MarketTransition::Close if early_close => MarketTransition::EarlyClose,
+                    transition => transition,
+                };
+                self.events.push_back(MarketEvent {
+                    transition,
+                    time: status.server_time,
+                    status: status.clone(),
+                    holiday,
+                });
+            }
+        }
+        self.session = Some((today, session));
+    }
+}
+
+impl ThrottledClient {
+    /// Poll the market status, and the holidays once per trading day. A failure to fetch the
+    /// holidays is returned after the status has been observed with the previous holidays, and
+    /// the holidays are fetched again on the next poll.
+    async fn poll_market(&self, watcher: &mut MarketWatcher) -> Result<()> {
+        let status = self.send(&GetMarketStatus).await?;
+        let today = status.server_time.with_timezone(&Eastern).date_naive();
+        let mut res = Ok(());
+        if watcher.holidays_date != Some(today) {
+            match self.send(&GetMarketHolidays).await {
+                Ok(holidays) => {
+                    watcher.holidays = holidays;
+                    watcher.holidays_date = Some(today);
+                }
+                Err(e) => res = Err(e),
+            }
+        }
+        watcher.observe(status);
+        res
+    }
+
+    /// Stream the transitions between trading sessions. The market status is polled while the
+    /// stream is polled, and an error polling it is yielded without ending the stream.
+    pub fn watch_market(
+        &self,
+        watch: &WatchMarket,
+    ) -> impl Stream<Item = Result<MarketEvent>> + Unpin + '_ {
+        let interval = watch.interval;
+        let watcher = MarketWatcher::default();
+        Box::pin(stream::unfold(
+            (watcher, true),
+            move |(mut watcher, mut first)| async move {
+                loop {
+                    if let Some(event) = watcher.events.pop_front() {
+                        return Some((Ok(event), (watcher, first)));
+                    }
+                    if !first {
+                        sleep(interval).await;
+                    }
+                    first = false;
+                    if let Err(e) = self.poll_market(&mut watcher).await {
+                        return Some((Err(e), (watcher, first)));
+                    }
+                }
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rest::RetryPolicy;
+    use mockito::{mock, Matcher};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn skipped_sessions_are_ordered() {
+        use MarketSession::*;
+        use MarketTransition::*;
+        assert_eq!(
+            Closed.transitions(Regular),
+            vec![PreMarketOpen, RegularOpen]
+        );
+        assert_eq!(Regular.transitions(Closed), vec![Close, AfterHoursEnd]);
+        assert_eq!(AfterHours.transitions(AfterHours), vec![]);
+    }
+
+    fn status(market: &str, time: &str) -> MarketStatus {
+        serde_json::from_str(&format!(
+            r#"{{"market":"{}","serverTime":"{}","exchanges":{{"nyse":"{}","nasdaq":"{}","otc":"closed"}},"currencies":{{"fx":"open","crypto":"open"}}}}"#,
+            market, time, market, market
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn sessions_across_trading_days() {
+        let mut watcher = MarketWatcher::default();
+        watcher.observe(status("open", "2021-03-01T15:00:00Z"));
+        watcher.observe(status("open", "2021-03-02T15:00:00Z"));
+        let transitions: Vec<_> = watcher.events.iter().map(|e| e.transition).collect();
+        assert_eq!(
+            transitions,
+            vec![
+                MarketTransition::Close,
+                MarketTransition::AfterHoursEnd,
+                MarketTransition::PreMarketOpen,
+                MarketTransition::RegularOpen
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn watch_with_failed_holidays() {
+        let statuses = [
+            ("open", "2021-03-01T15:00:00Z"),
+            ("closed", "2021-03-01T22:01:00Z"),
+        ];
+        let polls = Arc::new(AtomicUsize::new(0));
+        let counter = polls.clone();
+        let _status = mock("GET", "/v1/marketstatus/now")
+            .match_query(Matcher::UrlEncoded("apiKey".into(), "HOLIDAYS".into()))
+            .with_body_from_fn(move |w| {
+                let i = counter.fetch_add(1, Ordering::SeqCst).min(statuses.len() - 1);
+                let (market, time) = statuses[i];
+                write!(
+                    w,
+                    r#"{{"market":"{}","serverTime":"{}","exchanges":{{"nyse":"{}","nasdaq":"{}","otc":"closed"}},"currencies":{{"fx":"open","crypto":"open"}}}}"#,
+                    market, time, market, market
+                )
+            })
+            .create();
+        let _holidays = mock("GET", "/v1/marketstatus/upcoming")
+            .match_query(Matcher::UrlEncoded("apiKey".into(), "HOLIDAYS".into()))
+            .with_status(500)
+            .create();
+
+        let client = ThrottledClient::builder("HOLIDAYS")
+            .base_url(mockito::server_url())
+            .retry_policy(RetryPolicy::none())
+            .build()
+            .unwrap();
+        let watch = WatchMarket::new().interval(Duration::from_millis(1));
+        let events: Vec<Result<MarketEvent>> = client.watch_market(&watch).take(4).collect().await;
+        assert!(events[0].is_err() && events[1].is_err());
+        let transitions: Vec<_> = events[2..]
+            .iter()
+            .map(|e| e.as_ref().unwrap().transition)
+            .collect();
+        assert_eq!(
+            transitions,
+            vec![MarketTransition::Close, MarketTransition::AfterHoursEnd]
+        );
+    }
+
+    #[tokio::test]
+    async fn watch_early_close() {
+        // The day after Thanksgiving in 2020 closed at 13:00 in New York
+        let statuses = [
+            ("extended-hours", "2020-11-27T13:00:00Z"),
+            ("open", "2020-11-27T14:31:00Z"),
+            ("extended-hours", "2020-11-27T18:01:00Z"),
+            ("closed", "2020-11-27T22:01:00Z"),
+        ];
+        let polls = Arc::new(AtomicUsize::new(0));
+        let counter = polls.clone();
+        let _status = mock("GET", "/v1/marketstatus/now")
+            .match_query(Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()))
+            .with_body_from_fn(move |w| {
+                let i = counter.fetch_add(1, Ordering::SeqCst).min(statuses.len() - 1);
+                let (market, time) = statuses[i];
+                write!(
+                    w,
+                    r#"{{"market":"{}","serverTime":"{}","exchanges":{{"nyse":"{}","nasdaq":"{}","otc":"closed"}},"currencies":{{"fx":"open","crypto":"open"}}}}"#,
+                    market, time, market, market
+                )
+            })
+            .create();
+        let _holidays = mock("GET", "/v1/marketstatus/upcoming")
+            .match_query(Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()))
+            .with_body(r#"[{"exchange":"NYSE","name":"Thanksgiving","date":"2020-11-27","status":"early-close","open":"2020-11-27T14:30:00.000Z","close":"2020-11-27T18:00:00.000Z"}]"#)
+            .expect(1)
+            .create();
+
+        let client = ThrottledClient::builder("TOKEN")
+            .base_url(mockito::server_url())
+            .build()
+            .unwrap();
+        let watch = WatchMarket::new().interval(Duration::from_millis(1));
+        let events: Vec<MarketEvent> = client
+            .watch_market(&watch)
+            .take(3)
+            .try_collect()
+            .await
+            .unwrap();
+        let transitions: Vec<_> = events.iter().map(|e| e.transition).collect();
+        assert_eq!(
+            transitions,
+            vec![
+                MarketTransition::RegularOpen,
+                MarketTransition::EarlyClose,
+                MarketTransition::AfterHoursEnd
+            ]
+        );
+        assert_eq!(events[1].holiday.as_ref().unwrap().name, "Thanksgiving");
+        assert_eq!(polls.load(Ordering::SeqCst), 4);
+        _holidays.assert();
+    }
+}
@@ -1,15 +1,40 @@
 use vila::Client;
+pub mod adjustments;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+mod cache;
+mod cursor;
 mod date_utils;
+mod download;
+mod http;
+pub mod indicators;
+mod market_watch;
 pub mod reference;
 pub mod stocks;
+mod throttle;
+mod ticks;
 
+pub use adjustments::*;
+pub use cache::ResponseCache;
+pub use cursor::CursorPaginationData;
+pub use download::*;
+pub use http::RetryPolicy;
+pub use indicators::*;
+pub use market_watch::*;
 pub use reference::*;
 pub use stocks::*;
+pub use throttle::*;
+pub use ticks::*;
 
+/// A plain HTTP client for [`Request`](vila::Request)s, without rate limiting, retries or
+/// caching. This is a legacy escape hatch that exposes the underlying HTTP client and its errors,
+/// which may change between releases; prefer [`ThrottledClient`] or the
+/// [`Polygon`](crate::Polygon) facade.
 pub fn client(token: &str) -> Client {
     Client::new("https://api.polygon.io").query_auth(vec![("apiKey", token)])
 }
 
+/// Like [`client`], for a different base URL.
 pub fn client_with_url(url: &str, token: &str) -> Client {
     Client::new(url).query_auth(vec![("apiKey", token)])
 }
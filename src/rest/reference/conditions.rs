@@ -0,0 +1,293 @@
+use super::AssetClass;
+use crate::rest::cursor::*;
+use crate::rest::SortOrder;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::iter::FromIterator;
+use vila::pagination::{query::*, *};
+use vila::{Request, RequestData};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ConditionType {
+    SaleCondition,
+    QuoteCondition,
+    SipGeneratedFlag,
+    FinancialStatusIndicator,
+    ShortSaleRestrictionIndicator,
+    SettlementCondition,
+    MarketCondition,
+    TradeThruExempt,
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum DataType {
+    Trade,
+    Bbo,
+    Nbbo,
+    #[serde(other)]
+    Other,
+}
+
+/// The codes used for a condition by each of the SIPs.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SipMapping {
+    #[serde(rename = "CTA")]
+    pub cta: Option<String>,
+    #[serde(rename = "OPRA")]
+    pub opra: Option<String>,
+    #[serde(rename = "UTP")]
+    pub utp: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UpdateRule {
+    pub updates_high_low: bool,
+    pub updates_open_close: bool,
+    pub updates_volume: bool,
+}
+
+/// How a trade with the condition should be treated when building aggregates, both for the
+/// consolidated tape and for the reporting market center.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UpdateRules {
+    pub consolidated: UpdateRule,
+    pub market_center: UpdateRule,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Condition {
+    pub id: u32,
+    pub r#type: ConditionType,
+    pub name: String,
+    pub abbreviation: Option<String>,
+    pub asset_class: AssetClass,
+    #[serde(default)]
+    pub data_types: Vec<DataType>,
+    pub description: Option<String>,
+    pub exchange: Option<u32>,
+    #[serde(default)]
+    pub legacy: bool,
+    pub sip_mapping: SipMapping,
+    pub update_rules: Option<UpdateRules>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ConditionsWrapper {
+    pub count: Option<usize>,
+    pub request_id: String,
+    #[serde(default)]
+    pub results: Vec<Condition>,
+    pub status: String,
+    pub next_url: Option<String>,
+}
+
+impl NextUrl for ConditionsWrapper {
+    fn next_url(&self) -> Option<&str> {
+        self.next_url.as_deref()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConditionsSort {
+    AssetClass,
+    Id,
+    Type,
+    Name,
+    DataTypes,
+    Legacy,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct GetConditions {
+    asset_class: Option<AssetClass>,
+    data_type: Option<DataType>,
+    id: Option<u32>,
+    sip: Option<String>,
+    order: Option<SortOrder>,
+    sort: Option<ConditionsSort>,
+    limit: Option<u32>,
+}
+
+impl GetConditions {
+    pub fn new() -> Self {
+        Self {
+            limit: Some(1000),
+            ..Default::default()
+        }
+    }
+
+    pub fn asset_class(mut self, asset_class: AssetClass) -> Self {
+        self.asset_class = Some(asset_class);
+        self
+    }
+
+    pub fn data_type(mut self, data_type: DataType) -> Self {
+        self.data_type = Some(data_type);
+        self
+    }
+
+    pub fn id(mut self, id: u32) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    pub fn sip<T: ToString>(mut self, sip: T) -> Self {
+        self.sip = Some(sip.to_string());
+        self
+    }
+
+    pub fn order(mut self, order: SortOrder) -> Self {
+        self.order = Some(order);
+        self
+    }
+
+    pub fn sort(mut self, sort: ConditionsSort) -> Self {
+        self.sort = Some(sort);
+        self
+    }
+
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+}
+
+impl Request for GetConditions {
+    type Data = Self;
+    type Response = ConditionsWrapper;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        "/v3/reference/conditions".into()
+    }
+
+    fn data(&self) -> RequestData<&Self> {
+        RequestData::Query(self)
+    }
+}
+
+impl PaginatedRequest for GetConditions {
+    type Data = CursorPaginationData;
+    type Paginator = QueryPaginator<ConditionsWrapper, CursorPaginationData>;
+
+    fn paginator(&self) -> Self::Paginator {
+        cursor_paginator()
+    }
+}
+
+/// Lookup table from the condition codes found in trades and quotes to Polygon's published
+/// definitions of those conditions.
+///
+/// Trade and quote conditions share the same ID space, so they are stored separately. The
+/// registry should be built from the conditions of a single asset class.
+#[derive(Clone, Debug, Default)]
+pub struct ConditionRegistry {
+    trade: HashMap<u32, Condition>,
+    quote: HashMap<u32, Condition>,
+}
+
+impl ConditionRegistry {
+    pub fn new(conditions: Vec<Condition>) -> Self {
+        conditions.into_iter().collect()
+    }
+
+    pub fn trade_condition<I: Into<u32>>(&self, id: I) -> Option<&Condition> {
+        self.trade.get(&id.into())
+    }
+
+    pub fn quote_condition<I: Into<u32>>(&self, id: I) -> Option<&Condition> {
+        self.quote.get(&id.into())
+    }
+
+    fn consolidated_rule(&self, id: u32) -> Option<UpdateRule> {
+        self.trade
+            .get(&id)
+            .and_then(|c| c.update_rules)
+            .map(|r| r.consolidated)
+    }
+
+    /// Whether a trade with the condition updates the consolidated high and low.
+    pub fn updates_high_low<I: Into<u32>>(&self, id: I) -> bool {
+        self.consolidated_rule(id.into())
+            .is_some_and(|r| r.updates_high_low)
+    }
+
+    /// Whether a trade with the condition updates the consolidated volume.
+    pub fn updates_volume<I: Into<u32>>(&self, id: I) -> bool {
+        self.consolidated_rule(id.into())
+            .is_some_and(|r| r.updates_volume)
+    }
+
+    /// Whether a trade with the condition updates the consolidated last price.
+    pub fn updates_last<I: Into<u32>>(&self, id: I) -> bool {
+        self.consolidated_rule(id.into())
+            .is_some_and(|r| r.updates_open_close)
+    }
+
+    /// Whether a trade with the given conditions is eligible to update the consolidated high, low
+    /// and last price. Trades with unknown conditions are not eligible.
+    pub fn is_eligible<I: Into<u32>, T: IntoIterator<Item = I>>(&self, conditions: T) -> bool {
+        conditions.into_iter().all(|c| {
+            let c = c.into();
+            self.updates_high_low(c) && self.updates_last(c)
+        })
+    }
+}
+
+impl FromIterator<Condition> for ConditionRegistry {
+    fn from_iter<T: IntoIterator<Item = Condition>>(iter: T) -> Self {
+        let mut registry = Self::default();
+        for condition in iter {
+            if condition.data_types.contains(&DataType::Trade) {
+                registry.trade.insert(condition.id, condition.clone());
+            }
+            if condition
+                .data_types
+                .iter()
+                .any(|t| matches!(t, DataType::Bbo | DataType::Nbbo))
+            {
+                registry.quote.insert(condition.id, condition);
+            }
+        }
+        registry
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rest::client_with_url;
+    use futures::TryStreamExt;
+    use mockito::{mock, Matcher};
+
+    #[tokio::test]
+    async fn get_conditions() {
+        let _m = mock("GET", "/v3/reference/conditions")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()),
+                Matcher::UrlEncoded("asset_class".into(), "stocks".into()),
+            ]))
+            .with_body(r#"{"count":3,"request_id":"4599a4e2ba5e2b4c5b5e5f6e8a2c1d0b","results":[{"asset_class":"stocks","data_types":["trade"],"id":0,"name":"Regular Trade","sip_mapping":{"CTA":"@","UTP":"@"},"type":"sale_condition","update_rules":{"consolidated":{"updates_high_low":true,"updates_open_close":true,"updates_volume":true},"market_center":{"updates_high_low":true,"updates_open_close":true,"updates_volume":true}}},{"asset_class":"stocks","data_types":["trade"],"id":37,"name":"Odd Lot Trade","sip_mapping":{"CTA":"I","UTP":"I"},"type":"sale_condition","update_rules":{"consolidated":{"updates_high_low":false,"updates_open_close":false,"updates_volume":true},"market_center":{"updates_high_low":false,"updates_open_close":false,"updates_volume":true}}},{"asset_class":"stocks","data_types":["bbo","nbbo"],"id":0,"name":"Regular","sip_mapping":{"CTA":"R","UTP":"R"},"type":"quote_condition"}],"status":"OK"}"#)
+            .create();
+        let url = mockito::server_url();
+
+        let client = client_with_url(&url, "TOKEN");
+        let req = GetConditions::new().asset_class(AssetClass::Stocks);
+        let pages: Vec<ConditionsWrapper> =
+            client.send_paginated(&req).try_collect().await.unwrap();
+        let registry: ConditionRegistry = pages.into_iter().flat_map(|p| p.results).collect();
+
+        assert_eq!(registry.trade_condition(0u8).unwrap().name, "Regular Trade");
+        assert_eq!(registry.quote_condition(0u8).unwrap().name, "Regular");
+        assert!(registry.updates_volume(37u8));
+        assert!(!registry.updates_high_low(37u8));
+        assert!(registry.is_eligible(vec![0u8]));
+        assert!(!registry.is_eligible(vec![0u8, 37]));
+        assert!(!registry.is_eligible(vec![12u8]));
+    }
+}
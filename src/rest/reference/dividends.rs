@@ -0,0 +1,257 @@
+use crate::common::Ticker;
+use crate::rest::cursor::*;
+use crate::rest::SortOrder;
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_repr::*;
+use std::borrow::Cow;
+use vila::pagination::{query::*, *};
+use vila::{Request, RequestData};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DividendType {
+    /// Consistent, recurring cash dividends.
+    #[serde(rename = "CD")]
+    CashDividend,
+    /// Special, non-recurring cash dividends.
+    #[serde(rename = "SC")]
+    SpecialCash,
+    /// Long-term capital gain distributions.
+    #[serde(rename = "LT")]
+    LongTermCapitalGain,
+    /// Short-term capital gain distributions.
+    #[serde(rename = "ST")]
+    ShortTermCapitalGain,
+    #[serde(other)]
+    Unknown,
+}
+
+/// The number of times per year the dividend is paid out.
+#[derive(Serialize_repr, Deserialize_repr, Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum DividendFrequency {
+    OneTime = 0,
+    Annually = 1,
+    BiAnnually = 2,
+    Quarterly = 4,
+    Monthly = 12,
+    BiMonthly = 24,
+    Weekly = 52,
+    #[serde(other)]
+    Unknown = u8::MAX,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Dividend {
+    pub cash_amount: Decimal,
+    pub currency: Option<String>,
+    pub declaration_date: Option<NaiveDate>,
+    pub dividend_type: DividendType,
+    pub ex_dividend_date: NaiveDate,
+    pub frequency: DividendFrequency,
+    pub pay_date: Option<NaiveDate>,
+    pub record_date: Option<NaiveDate>,
+    pub ticker: Ticker,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DividendsWrapper {
+    #[serde(default)]
+    pub results: Vec<Dividend>,
+    pub status: String,
+    pub request_id: String,
+    pub next_url: Option<String>,
+}
+
+impl NextUrl for DividendsWrapper {
+    fn next_url(&self) -> Option<&str> {
+        self.next_url.as_deref()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DividendsSort {
+    ExDividendDate,
+    PayDate,
+    DeclarationDate,
+    RecordDate,
+    CashAmount,
+    Ticker,
+}
+
+/// Request historical cash dividends across all tickers, optionally filtered by ticker, dates,
+/// type and frequency.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct GetDividends {
+    ticker: Option<Ticker>,
+    ex_dividend_date: Option<NaiveDate>,
+    #[serde(rename = "ex_dividend_date.gte")]
+    ex_dividend_date_gte: Option<NaiveDate>,
+    #[serde(rename = "ex_dividend_date.lte")]
+    ex_dividend_date_lte: Option<NaiveDate>,
+    pay_date: Option<NaiveDate>,
+    #[serde(rename = "pay_date.gte")]
+    pay_date_gte: Option<NaiveDate>,
+    #[serde(rename = "pay_date.lte")]
+    pay_date_lte: Option<NaiveDate>,
+    declaration_date: Option<NaiveDate>,
+    #[serde(rename = "declaration_date.gte")]
+    declaration_date_gte: Option<NaiveDate>,
+    #[serde(rename = "declaration_date.lte")]
+    declaration_date_lte: Option<NaiveDate>,
+    dividend_type: Option<DividendType>,
+    frequency: Option<DividendFrequency>,
+    order: Option<SortOrder>,
+    sort: Option<DividendsSort>,
+    limit: Option<u32>,
+}
+
+impl GetDividends {
+    pub fn new() -> Self {
+        Self {
+            limit: Some(1000),
+            ..Default::default()
+        }
+    }
+
+    pub fn ticker<T: Into<Ticker>>(mut self, ticker: T) -> Self {
+        self.ticker = Some(ticker.into());
+        self
+    }
+
+    pub fn ex_dividend_date(mut self, date: NaiveDate) -> Self {
+        self.ex_dividend_date = Some(date);
+        self
+    }
+
+    pub fn ex_dividend_date_gte(mut self, date: NaiveDate) -> Self {
+        self.ex_dividend_date_gte = Some(date);
+        self
+    }
+
+    pub fn ex_dividend_date_lte(mut self, date: NaiveDate) -> Self {
+        self.ex_dividend_date_lte = Some(date);
+        self
+    }
+
+    pub fn pay_date(mut self, date: NaiveDate) -> Self {
+        self.pay_date = Some(date);
+        self
+    }
+
+    pub fn pay_date_gte(mut self, date: NaiveDate) -> Self {
+        self.pay_date_gte = Some(date);
+        self
+    }
+
+    pub fn pay_date_lte(mut self, date: NaiveDate) -> Self {
+        self.pay_date_lte = Some(date);
+        self
+    }
+
+    pub fn declaration_date(mut self, date: NaiveDate) -> Self {
+        self.declaration_date = Some(date);
+        self
+    }
+
+    pub fn declaration_date_gte(mut self, date: NaiveDate) -> Self {
+        self.declaration_date_gte = Some(date);
+        self
+    }
+
+    pub fn declaration_date_lte(mut self, date: NaiveDate) -> Self {
+        self.declaration_date_lte = Some(date);
+        self
+    }
+
+    pub fn dividend_type(mut self, dividend_type: DividendType) -> Self {
+        self.dividend_type = Some(dividend_type);
+        self
+    }
+
+    pub fn frequency(mut self, frequency: DividendFrequency) -> Self {
+        self.frequency = Some(frequency);
+        self
+    }
+
+    pub fn order(mut self, order: SortOrder) -> Self {
+        self.order = Some(order);
+        self
+    }
+
+    pub fn sort(mut self, sort: DividendsSort) -> Self {
+        self.sort = Some(sort);
+        self
+    }
+
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+}
+
+impl Request for GetDividends {
+    type Data = Self;
+    type Response = DividendsWrapper;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        "/v3/reference/dividends".into()
+    }
+
+    fn data(&self) -> RequestData<&Self> {
+        RequestData::Query(self)
+    }
+}
+
+impl PaginatedRequest for GetDividends {
+    type Data = CursorPaginationData;
+    type Paginator = QueryPaginator<DividendsWrapper, CursorPaginationData>;
+
+    fn paginator(&self) -> Self::Paginator {
+        cursor_paginator()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rest::client_with_url;
+    use futures::TryStreamExt;
+    use mockito::{mock, Matcher};
+    use rust_decimal_macros::dec;
+
+    #[tokio::test]
+    async fn get_dividends() {
+        let _m = mock("GET", "/v3/reference/dividends")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()),
+                Matcher::UrlEncoded("ticker".into(), "AAPL".into()),
+                Matcher::UrlEncoded("ex_dividend_date.gte".into(), "2021-01-01".into()),
+                Matcher::UrlEncoded("dividend_type".into(), "CD".into()),
+                Matcher::UrlEncoded("limit".into(), "1000".into()),
+            ]))
+            .with_body(r#"{"results":[{"cash_amount":0.22,"currency":"USD","declaration_date":"2021-10-28","dividend_type":"CD","ex_dividend_date":"2021-11-05","frequency":4,"pay_date":"2021-11-11","record_date":"2021-11-08","ticker":"AAPL"}],"status":"OK","request_id":"eba0ba7b3de0fd7c2b5dfd5fd1b3be3a"}"#)
+            .create();
+        let url = mockito::server_url();
+
+        let client = client_with_url(&url, "TOKEN");
+        let req = GetDividends::new()
+            .ticker("AAPL")
+            .ex_dividend_date_gte(NaiveDate::from_ymd_opt(2021, 1, 1).unwrap())
+            .dividend_type(DividendType::CashDividend);
+        let pages: Vec<DividendsWrapper> = client.send_paginated(&req).try_collect().await.unwrap();
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].results[0].cash_amount, dec!(0.22));
+        assert_eq!(pages[0].results[0].frequency, DividendFrequency::Quarterly);
+    }
+
+    #[test]
+    fn unknown_dividend_kinds() {
+        let kind: DividendType = serde_json::from_str(r#""XX""#).unwrap();
+        assert_eq!(kind, DividendType::Unknown);
+        let frequency: DividendFrequency = serde_json::from_str("3").unwrap();
+        assert_eq!(frequency, DividendFrequency::Unknown);
+    }
+}
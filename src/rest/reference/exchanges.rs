@@ -0,0 +1,167 @@
+use super::{AssetClass, Locale};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::iter::FromIterator;
+use vila::{Request, RequestData};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExchangeType {
+    #[serde(rename = "exchange")]
+    Exchange,
+    /// A trade reporting facility, through which off-exchange trades are reported.
+    #[serde(rename = "TRF")]
+    Trf,
+    /// A securities information processor, which consolidates data from the other venues.
+    #[serde(rename = "SIP")]
+    Sip,
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Exchange {
+    /// The ID used by Polygon to identify the venue, e.g. in `Quote.bid_exchange` or
+    /// `Trade.exchange_id`.
+    pub id: u32,
+    pub r#type: ExchangeType,
+    pub asset_class: AssetClass,
+    pub locale: Locale,
+    pub name: String,
+    pub acronym: Option<String>,
+    pub mic: Option<String>,
+    pub operating_mic: Option<String>,
+    /// The identifier used for the venue on the consolidated tapes.
+    pub participant_id: Option<String>,
+    pub url: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ExchangesWrapper {
+    pub count: Option<usize>,
+    pub request_id: String,
+    #[serde(default)]
+    pub results: Vec<Exchange>,
+    pub status: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct GetExchanges {
+    asset_class: Option<AssetClass>,
+    locale: Option<Locale>,
+}
+
+impl GetExchanges {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn asset_class(mut self, asset_class: AssetClass) -> Self {
+        self.asset_class = Some(asset_class);
+        self
+    }
+
+    pub fn locale(mut self, locale: Locale) -> Self {
+        self.locale = Some(locale);
+        self
+    }
+}
+
+impl Request for GetExchanges {
+    type Data = Self;
+    type Response = ExchangesWrapper;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        "/v3/reference/exchanges".into()
+    }
+
+    fn data(&self) -> RequestData<&Self> {
+        RequestData::Query(self)
+    }
+}
+
+/// Lookup table from the exchange IDs found in trades and quotes to the venues they represent.
+#[derive(Clone, Debug, Default)]
+pub struct ExchangeRegistry {
+    exchanges: HashMap<u32, Exchange>,
+}
+
+impl ExchangeRegistry {
+    pub fn new(exchanges: Vec<Exchange>) -> Self {
+        exchanges.into_iter().collect()
+    }
+
+    pub fn get<I: Into<u32>>(&self, id: I) -> Option<&Exchange> {
+        self.exchanges.get(&id.into())
+    }
+
+    pub fn mic<I: Into<u32>>(&self, id: I) -> Option<&str> {
+        self.get(id).and_then(|e| e.mic.as_deref())
+    }
+
+    pub fn name<I: Into<u32>>(&self, id: I) -> Option<&str> {
+        self.get(id).map(|e| e.name.as_str())
+    }
+
+    pub fn participant_id<I: Into<u32>>(&self, id: I) -> Option<&str> {
+        self.get(id).and_then(|e| e.participant_id.as_deref())
+    }
+
+    /// The code of the venue on the consolidated tapes, which is its participant ID.
+    pub fn tape<I: Into<u32>>(&self, id: I) -> Option<&str> {
+        self.participant_id(id)
+    }
+
+    /// Find a venue by its MIC.
+    pub fn by_mic(&self, mic: &str) -> Option<&Exchange> {
+        self.exchanges
+            .values()
+            .find(|e| e.mic.as_deref() == Some(mic))
+    }
+
+    /// Find a venue by its code on the consolidated tapes, e.g. `N` for the NYSE.
+    pub fn by_tape(&self, code: &str) -> Option<&Exchange> {
+        self.exchanges
+            .values()
+            .find(|e| e.participant_id.as_deref() == Some(code))
+    }
+}
+
+impl FromIterator<Exchange> for ExchangeRegistry {
+    fn from_iter<T: IntoIterator<Item = Exchange>>(iter: T) -> Self {
+        Self {
+            exchanges: iter.into_iter().map(|e| (e.id, e)).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rest::client_with_url;
+    use mockito::{mock, Matcher};
+
+    #[tokio::test]
+    async fn get_exchanges() {
+        let _m = mock("GET", "/v3/reference/exchanges")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()),
+                Matcher::UrlEncoded("asset_class".into(), "stocks".into()),
+            ]))
+            .with_body(r#"{"count":2,"request_id":"236386d2a6a6b4a0a5d1e3ec1c1a5f88","results":[{"acronym":"NYSE","asset_class":"stocks","id":10,"locale":"us","mic":"XNYS","name":"New York Stock Exchange","operating_mic":"XNYS","participant_id":"N","type":"exchange","url":"https://www.nyse.com"},{"asset_class":"stocks","id":4,"locale":"us","mic":"FINN","name":"FINRA NYSE TRF","operating_mic":"FINR","participant_id":"D","type":"TRF"}],"status":"OK"}"#)
+            .create();
+        let url = mockito::server_url();
+
+        let client = client_with_url(&url, "TOKEN");
+        let req = GetExchanges::new().asset_class(AssetClass::Stocks);
+        let registry = ExchangeRegistry::new(client.send(&req).await.unwrap().results);
+        assert_eq!(registry.mic(10u8), Some("XNYS"));
+        assert_eq!(registry.name(4u8), Some("FINRA NYSE TRF"));
+        assert_eq!(registry.get(4u8).unwrap().r#type, ExchangeType::Trf);
+        assert_eq!(registry.participant_id(10u8), Some("N"));
+        assert_eq!(registry.tape(4u8), Some("D"));
+        assert_eq!(registry.by_tape("N").map(|e| e.id), Some(10));
+        assert_eq!(registry.by_mic("FINN").map(|e| e.id), Some(4));
+        assert!(registry.get(99u8).is_none());
+    }
+}
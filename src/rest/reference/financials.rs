@@ -0,0 +1,314 @@
+use crate::common::Ticker;
+use crate::rest::cursor::*;
+use crate::rest::SortOrder;
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use vila::pagination::{query::*, *};
+use vila::{Request, RequestData};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Timeframe {
+    Annual,
+    Quarterly,
+    Ttm,
+}
+
+/// A single labelled value in a financial statement.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DataPoint {
+    pub label: String,
+    pub order: i32,
+    pub unit: String,
+    pub value: Decimal,
+    /// The XBRL path of the value in the source filing, if it was reported directly.
+    pub xpath: Option<String>,
+    /// The names of the values this value was derived from, if it was not reported directly.
+    pub derived_from: Option<Vec<String>>,
+    pub formula: Option<String>,
+}
+
+/// Values that are not explicitly modelled by the statement types are kept in `other`, keyed by
+/// the name Polygon uses for them.
+pub type OtherDataPoints = HashMap<String, DataPoint>;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct BalanceSheet {
+    pub assets: Option<DataPoint>,
+    pub current_assets: Option<DataPoint>,
+    pub noncurrent_assets: Option<DataPoint>,
+    pub fixed_assets: Option<DataPoint>,
+    pub liabilities: Option<DataPoint>,
+    pub current_liabilities: Option<DataPoint>,
+    pub noncurrent_liabilities: Option<DataPoint>,
+    pub equity: Option<DataPoint>,
+    pub equity_attributable_to_parent: Option<DataPoint>,
+    pub equity_attributable_to_noncontrolling_interest: Option<DataPoint>,
+    pub liabilities_and_equity: Option<DataPoint>,
+    #[serde(flatten)]
+    pub other: OtherDataPoints,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct IncomeStatement {
+    pub revenues: Option<DataPoint>,
+    pub cost_of_revenue: Option<DataPoint>,
+    pub gross_profit: Option<DataPoint>,
+    pub operating_expenses: Option<DataPoint>,
+    pub operating_income_loss: Option<DataPoint>,
+    pub income_loss_from_continuing_operations_before_tax: Option<DataPoint>,
+    pub income_tax_expense_benefit: Option<DataPoint>,
+    pub net_income_loss: Option<DataPoint>,
+    pub net_income_loss_attributable_to_parent: Option<DataPoint>,
+    pub basic_earnings_per_share: Option<DataPoint>,
+    pub diluted_earnings_per_share: Option<DataPoint>,
+    #[serde(flatten)]
+    pub other: OtherDataPoints,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct CashFlowStatement {
+    pub net_cash_flow: Option<DataPoint>,
+    pub net_cash_flow_continuing: Option<DataPoint>,
+    pub net_cash_flow_from_operating_activities: Option<DataPoint>,
+    pub net_cash_flow_from_investing_activities: Option<DataPoint>,
+    pub net_cash_flow_from_financing_activities: Option<DataPoint>,
+    #[serde(flatten)]
+    pub other: OtherDataPoints,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ComprehensiveIncome {
+    pub comprehensive_income_loss: Option<DataPoint>,
+    pub comprehensive_income_loss_attributable_to_parent: Option<DataPoint>,
+    pub other_comprehensive_income_loss: Option<DataPoint>,
+    #[serde(flatten)]
+    pub other: OtherDataPoints,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct FinancialStatements {
+    pub balance_sheet: Option<BalanceSheet>,
+    pub income_statement: Option<IncomeStatement>,
+    pub cash_flow_statement: Option<CashFlowStatement>,
+    pub comprehensive_income: Option<ComprehensiveIncome>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Financials {
+    pub cik: Option<String>,
+    pub company_name: Option<String>,
+    #[serde(default)]
+    pub tickers: Vec<String>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub filing_date: Option<NaiveDate>,
+    pub fiscal_period: String,
+    pub fiscal_year: Option<String>,
+    pub timeframe: Timeframe,
+    pub source_filing_url: Option<String>,
+    pub source_filing_file_url: Option<String>,
+    pub financials: FinancialStatements,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FinancialsWrapper {
+    pub count: Option<usize>,
+    #[serde(default)]
+    pub results: Vec<Financials>,
+    pub status: String,
+    pub request_id: String,
+    pub next_url: Option<String>,
+}
+
+impl NextUrl for FinancialsWrapper {
+    fn next_url(&self) -> Option<&str> {
+        self.next_url.as_deref()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FinancialsSort {
+    FilingDate,
+    PeriodOfReportDate,
+}
+
+/// Request financial statements derived from SEC filings.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct GetFinancials {
+    ticker: Option<Ticker>,
+    cik: Option<String>,
+    company_name: Option<String>,
+    filing_date: Option<NaiveDate>,
+    #[serde(rename = "filing_date.gte")]
+    filing_date_gte: Option<NaiveDate>,
+    #[serde(rename = "filing_date.lte")]
+    filing_date_lte: Option<NaiveDate>,
+    period_of_report_date: Option<NaiveDate>,
+    #[serde(rename = "period_of_report_date.gte")]
+    period_of_report_date_gte: Option<NaiveDate>,
+    #[serde(rename = "period_of_report_date.lte")]
+    period_of_report_date_lte: Option<NaiveDate>,
+    timeframe: Option<Timeframe>,
+    include_sources: Option<bool>,
+    order: Option<SortOrder>,
+    sort: Option<FinancialsSort>,
+    limit: Option<u32>,
+}
+
+impl GetFinancials {
+    pub fn new() -> Self {
+        Self {
+            limit: Some(100),
+            ..Default::default()
+        }
+    }
+
+    pub fn ticker<T: Into<Ticker>>(mut self, ticker: T) -> Self {
+        self.ticker = Some(ticker.into());
+        self
+    }
+
+    pub fn cik<T: ToString>(mut self, cik: T) -> Self {
+        self.cik = Some(cik.to_string());
+        self
+    }
+
+    pub fn company_name<T: ToString>(mut self, company_name: T) -> Self {
+        self.company_name = Some(company_name.to_string());
+        self
+    }
+
+    pub fn filing_date(mut self, date: NaiveDate) -> Self {
+        self.filing_date = Some(date);
+        self
+    }
+
+    pub fn filing_date_gte(mut self, date: NaiveDate) -> Self {
+        self.filing_date_gte = Some(date);
+        self
+    }
+
+    pub fn filing_date_lte(mut self, date: NaiveDate) -> Self {
+        self.filing_date_lte = Some(date);
+        self
+    }
+
+    pub fn period_of_report_date(mut self, date: NaiveDate) -> Self {
+        self.period_of_report_date = Some(date);
+        self
+    }
+
+    pub fn period_of_report_date_gte(mut self, date: NaiveDate) -> Self {
+        self.period_of_report_date_gte = Some(date);
+        self
+    }
+
+    pub fn period_of_report_date_lte(mut self, date: NaiveDate) -> Self {
+        self.period_of_report_date_lte = Some(date);
+        self
+    }
+
+    pub fn timeframe(mut self, timeframe: Timeframe) -> Self {
+        self.timeframe = Some(timeframe);
+        self
+    }
+
+    /// Include the XBRL path or derivation of every value in the response.
+    pub fn include_sources(mut self, include_sources: bool) -> Self {
+        self.include_sources = Some(include_sources);
+        self
+    }
+
+    pub fn order(mut self, order: SortOrder) -> Self {
+        self.order = Some(order);
+        self
+    }
+
+    pub fn sort(mut self, sort: FinancialsSort) -> Self {
+        self.sort = Some(sort);
+        self
+    }
+
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+}
+
+impl Request for GetFinancials {
+    type Data = Self;
+    type Response = FinancialsWrapper;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        "/vX/reference/financials".into()
+    }
+
+    fn data(&self) -> RequestData<&Self> {
+        RequestData::Query(self)
+    }
+}
+
+impl PaginatedRequest for GetFinancials {
+    type Data = CursorPaginationData;
+    type Paginator = QueryPaginator<FinancialsWrapper, CursorPaginationData>;
+
+    fn paginator(&self) -> Self::Paginator {
+        cursor_paginator()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rest::client_with_url;
+    use mockito::{mock, Matcher};
+    use rust_decimal_macros::dec;
+
+    #[tokio::test]
+    async fn get_financials() {
+        let _m = mock("GET", "/vX/reference/financials")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()),
+                Matcher::UrlEncoded("ticker".into(), "AAPL".into()),
+                Matcher::UrlEncoded("timeframe".into(), "quarterly".into()),
+                Matcher::UrlEncoded("include_sources".into(), "true".into()),
+                Matcher::UrlEncoded("filing_date.gte".into(), "2022-01-01".into()),
+            ]))
+            .with_body(r#"{"count":1,"request_id":"55eb92ed43b25568ab0cce159830ea34","results":[{"cik":"0000320193","company_name":"Apple Inc.","end_date":"2022-06-25","filing_date":"2022-07-29","financials":{"balance_sheet":{"assets":{"label":"Assets","order":100,"unit":"USD","value":336309000000,"xpath":"//*[local-name()='Assets']"},"equity":{"label":"Equity","order":1400,"unit":"USD","value":58107000000,"derived_from":["equity_attributable_to_parent"]},"other_than_fixed_noncurrent_assets":{"label":"Other Than Fixed Noncurrent Assets","order":500,"unit":"USD","value":86803000000}},"cash_flow_statement":{"net_cash_flow":{"label":"Net Cash Flow","order":1100,"unit":"USD","value":-1450000000}},"comprehensive_income":{"comprehensive_income_loss":{"label":"Comprehensive Income/Loss","order":100,"unit":"USD","value":17674000000}},"income_statement":{"basic_earnings_per_share":{"label":"Basic Earnings Per Share","order":4200,"unit":"USD / shares","value":1.2},"revenues":{"label":"Revenues","order":100,"unit":"USD","value":82959000000}}},"fiscal_period":"Q3","fiscal_year":"2022","source_filing_file_url":"https://api.polygon.io/v1/reference/sec/filings/0000320193-22-000070/files/aapl-20220625_htm.xml","source_filing_url":"https://api.polygon.io/v1/reference/sec/filings/0000320193-22-000070","start_date":"2022-03-27","tickers":["AAPL"],"timeframe":"quarterly"}],"status":"OK"}"#)
+            .create();
+        let url = mockito::server_url();
+
+        let client = client_with_url(&url, "TOKEN");
+        let req = GetFinancials::new()
+            .ticker("AAPL")
+            .timeframe(Timeframe::Quarterly)
+            .include_sources(true)
+            .filing_date_gte(NaiveDate::from_ymd_opt(2022, 1, 1).unwrap());
+        let financials = client.send(&req).await.unwrap();
+        let statements = &financials.results[0].financials;
+
+        let balance_sheet = statements.balance_sheet.as_ref().unwrap();
+        assert_eq!(
+            balance_sheet.assets.as_ref().unwrap().value,
+            dec!(336309000000)
+        );
+        assert_eq!(
+            balance_sheet.other["other_than_fixed_noncurrent_assets"].label,
+            "Other Than Fixed Noncurrent Assets"
+        );
+        let income_statement = statements.income_statement.as_ref().unwrap();
+        let eps = income_statement.basic_earnings_per_share.as_ref().unwrap();
+        assert_eq!(eps.value, dec!(1.2));
+        assert_eq!(eps.unit, "USD / shares");
+        let cash_flow = statements.cash_flow_statement.as_ref().unwrap();
+        assert_eq!(
+            cash_flow.net_cash_flow.as_ref().unwrap().value,
+            dec!(-1450000000)
+        );
+    }
+}
@@ -24,6 +24,7 @@ pub struct MarketHoliday {
     pub date: NaiveDate,
 }
 
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct GetMarketHolidays;
 
 impl Request for GetMarketHolidays {
@@ -35,6 +35,7 @@ pub struct MarketStatus {
     pub currencies: CurrenciesStatus,
 }
 
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct GetMarketStatus;
 
 impl Request for GetMarketStatus {
@@ -1,13 +1,25 @@
+mod conditions;
+mod dividends;
+mod exchanges;
+mod financials;
 mod market_holidays;
 mod market_status;
+mod splits;
 mod stock_dividends;
 mod stock_splits;
 mod ticker_details;
+mod ticker_news;
 mod ticker_types;
 
+pub use conditions::*;
+pub use dividends::*;
+pub use exchanges::*;
+pub use financials::*;
 pub use market_holidays::*;
 pub use market_status::*;
+pub use splits::*;
 pub use stock_dividends::*;
 pub use stock_splits::*;
 pub use ticker_details::*;
+pub use ticker_news::*;
 pub use ticker_types::*;
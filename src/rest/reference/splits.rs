@@ -0,0 +1,180 @@
+use crate::common::Ticker;
+use crate::rest::cursor::*;
+use crate::rest::SortOrder;
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use vila::pagination::{query::*, *};
+use vila::{Request, RequestData};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Split {
+    pub execution_date: NaiveDate,
+    pub split_from: Decimal,
+    pub split_to: Decimal,
+    pub ticker: Ticker,
+}
+
+impl Split {
+    /// The factor by which historical prices are multiplied to adjust for the split, e.g. 0.25 for
+    /// a 4-for-1 split, or `None` if `split_to` is zero.
+    pub fn ratio(&self) -> Option<Decimal> {
+        self.split_from.checked_div(self.split_to)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SplitsWrapper {
+    #[serde(default)]
+    pub results: Vec<Split>,
+    pub status: String,
+    pub request_id: String,
+    pub next_url: Option<String>,
+}
+
+impl NextUrl for SplitsWrapper {
+    fn next_url(&self) -> Option<&str> {
+        self.next_url.as_deref()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SplitsSort {
+    ExecutionDate,
+    Ticker,
+}
+
+/// Request historical stock splits across all tickers, optionally filtered by ticker and
+/// execution date.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct GetSplits {
+    ticker: Option<Ticker>,
+    execution_date: Option<NaiveDate>,
+    #[serde(rename = "execution_date.gte")]
+    execution_date_gte: Option<NaiveDate>,
+    #[serde(rename = "execution_date.lte")]
+    execution_date_lte: Option<NaiveDate>,
+    reverse_split: Option<bool>,
+    order: Option<SortOrder>,
+    sort: Option<SplitsSort>,
+    limit: Option<u32>,
+}
+
+impl GetSplits {
+    pub fn new() -> Self {
+        Self {
+            limit: Some(1000),
+            ..Default::default()
+        }
+    }
+
+    pub fn ticker<T: Into<Ticker>>(mut self, ticker: T) -> Self {
+        self.ticker = Some(ticker.into());
+        self
+    }
+
+    pub fn execution_date(mut self, date: NaiveDate) -> Self {
+        self.execution_date = Some(date);
+        self
+    }
+
+    pub fn execution_date_gte(mut self, date: NaiveDate) -> Self {
+        self.execution_date_gte = Some(date);
+        self
+    }
+
+    pub fn execution_date_lte(mut self, date: NaiveDate) -> Self {
+        self.execution_date_lte = Some(date);
+        self
+    }
+
+    pub fn reverse_split(mut self, reverse_split: bool) -> Self {
+        self.reverse_split = Some(reverse_split);
+        self
+    }
+
+    pub fn order(mut self, order: SortOrder) -> Self {
+        self.order = Some(order);
+        self
+    }
+
+    pub fn sort(mut self, sort: SplitsSort) -> Self {
+        self.sort = Some(sort);
+        self
+    }
+
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+}
+
+impl Request for GetSplits {
+    type Data = Self;
+    type Response = SplitsWrapper;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        "/v3/reference/splits".into()
+    }
+
+    fn data(&self) -> RequestData<&Self> {
+        RequestData::Query(self)
+    }
+}
+
+impl PaginatedRequest for GetSplits {
+    type Data = CursorPaginationData;
+    type Paginator = QueryPaginator<SplitsWrapper, CursorPaginationData>;
+
+    fn paginator(&self) -> Self::Paginator {
+        cursor_paginator()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rest::client_with_url;
+    use futures::TryStreamExt;
+    use mockito::{mock, Matcher};
+    use rust_decimal_macros::dec;
+
+    #[tokio::test]
+    async fn get_splits_paginated() {
+        let url = mockito::server_url();
+        let _first = mock("GET", "/v3/reference/splits")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()),
+                Matcher::UrlEncoded("execution_date.lte".into(), "2021-01-01".into()),
+                Matcher::UrlEncoded("limit".into(), "1".into()),
+            ]))
+            .with_body(format!(r#"{{"next_url":"{}/v3/reference/splits?cursor=YWN0aXZlPXRydWU%3D","request_id":"6a7e466379af0a71039d60cc78e72282","results":[{{"execution_date":"2020-08-31","split_from":1,"split_to":4,"ticker":"AAPL"}}],"status":"OK"}}"#, url))
+            .create();
+        let _second = mock("GET", "/v3/reference/splits")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()),
+                Matcher::UrlEncoded("cursor".into(), "YWN0aXZlPXRydWU=".into()),
+            ]))
+            .with_body(r#"{"request_id":"6a7e466379af0a71039d60cc78e72283","results":[{"execution_date":"2014-06-09","split_from":1,"split_to":7,"ticker":"AAPL"}],"status":"OK"}"#)
+            .create();
+
+        let client = client_with_url(&url, "TOKEN");
+        let req = GetSplits::new()
+            .execution_date_lte(NaiveDate::from_ymd_opt(2021, 1, 1).unwrap())
+            .limit(1);
+        let pages: Vec<SplitsWrapper> = client.send_paginated(&req).try_collect().await.unwrap();
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].results[0].ratio(), Some(dec!(0.25)));
+        let invalid = Split {
+            split_to: Decimal::ZERO,
+            ..pages[0].results[0].clone()
+        };
+        assert_eq!(invalid.ratio(), None);
+        assert_eq!(
+            pages[1].results[0].execution_date,
+            NaiveDate::from_ymd_opt(2014, 6, 9).unwrap()
+        );
+    }
+}
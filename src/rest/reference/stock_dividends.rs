@@ -1,3 +1,4 @@
+use crate::common::Ticker;
 use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
@@ -10,7 +11,7 @@ pub struct StockDividend {
     pub ex_date: NaiveDate,
     pub payment_date: NaiveDate,
     pub record_date: NaiveDate,
-    pub ticker: String,
+    pub ticker: Ticker,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -23,7 +24,15 @@ pub struct StockDividendsWrapper {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct GetStockDividends {
     #[serde(rename = "stocksTicker")]
-    pub stocks_ticker: String,
+    pub stocks_ticker: Ticker,
+}
+
+impl GetStockDividends {
+    pub fn new<T: Into<Ticker>>(ticker: T) -> Self {
+        Self {
+            stocks_ticker: ticker.into(),
+        }
+    }
 }
 
 impl Request for GetStockDividends {
@@ -52,7 +61,7 @@ mod test {
 
         let client = client_with_url(&url, "TOKEN");
         let req = GetStockDividends {
-            stocks_ticker: "AAPL".to_string(),
+            stocks_ticker: "AAPL".into(),
         };
         client.send(&req).await.unwrap();
     }
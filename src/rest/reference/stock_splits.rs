@@ -1,3 +1,4 @@
+use crate::common::Ticker;
 use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
@@ -12,7 +13,7 @@ pub struct StockSplit {
     pub declared_date: Option<NaiveDate>,
     pub ex_date: NaiveDate,
     pub payment_date: NaiveDate,
-    pub ticker: String,
+    pub ticker: Ticker,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -25,7 +26,15 @@ pub struct StockSplitsWrapper {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct GetStockSplits {
     #[serde(rename = "stocksTicker")]
-    pub stocks_ticker: String,
+    pub stocks_ticker: Ticker,
+}
+
+impl GetStockSplits {
+    pub fn new<T: Into<Ticker>>(ticker: T) -> Self {
+        Self {
+            stocks_ticker: ticker.into(),
+        }
+    }
 }
 
 impl Request for GetStockSplits {
@@ -54,7 +63,7 @@ mod test {
 
         let client = client_with_url(&url, "TOKEN");
         let req = GetStockSplits {
-            stocks_ticker: "AAPL".to_string(),
+            stocks_ticker: "AAPL".into(),
         };
         client.send(&req).await.unwrap();
     }
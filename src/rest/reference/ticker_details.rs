@@ -1,5 +1,7 @@
 use super::Locale;
+use crate::common::Ticker;
 use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use uuid::Uuid;
@@ -11,48 +13,96 @@ pub enum Market {
     Stocks,
     Crypto,
     Fx,
+    Otc,
+    Indices,
 }
 
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Address {
+    pub address1: Option<String>,
+    pub address2: Option<String>,
+    pub city: Option<String>,
+    pub state: Option<String>,
+    pub postal_code: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Branding {
+    pub logo_url: Option<String>,
+    pub icon_url: Option<String>,
+}
+
+/// Details for a single ticker. Polygon omits many fields for ETFs, OTC securities and delisted
+/// tickers, so everything that is not guaranteed to be present is optional.
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct TickerDetails {
-    pub cik: String,
-    pub composite_figi: String,
-    pub currency_name: String,
-    pub last_updated_utc: DateTime<Utc>,
+    pub active: bool,
+    pub address: Option<Address>,
+    pub branding: Option<Branding>,
+    pub cik: Option<String>,
+    pub composite_figi: Option<String>,
+    pub currency_name: Option<String>,
+    pub delisted_utc: Option<DateTime<Utc>>,
+    pub description: Option<String>,
+    pub homepage_url: Option<String>,
+    pub last_updated_utc: Option<DateTime<Utc>>,
+    pub list_date: Option<NaiveDate>,
     pub locale: Locale,
     pub market: Market,
-    pub market_cap: usize,
+    pub market_cap: Option<Decimal>,
     pub name: String,
-    pub outstanding_shares: usize,
-    pub phone_number: String,
-    pub primary_exchange: String,
-    pub share_class_figi: String,
-    pub sic_code: String,
-    pub sic_description: String,
-    pub ticker: String,
-    pub r#type: String,
+    pub outstanding_shares: Option<u64>,
+    pub phone_number: Option<String>,
+    pub primary_exchange: Option<String>,
+    pub round_lot: Option<u32>,
+    pub share_class_figi: Option<String>,
+    pub share_class_shares_outstanding: Option<u64>,
+    pub sic_code: Option<String>,
+    pub sic_description: Option<String>,
+    pub ticker: Ticker,
+    pub ticker_root: Option<String>,
+    pub ticker_suffix: Option<String>,
+    pub total_employees: Option<u64>,
+    pub r#type: Option<String>,
+    pub weighted_shares_outstanding: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct TickerDetailsWrapper {
-    pub count: usize,
+    pub count: Option<usize>,
     pub request_id: Uuid,
     pub results: TickerDetails,
     pub status: String,
 }
 
+/// Request details for a ticker. If a `date` is given, the details are returned as they were on
+/// that date, which allows looking up tickers that have since been delisted or renamed.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct GetTickerDetails {
-    ticker: String,
+    ticker: Ticker,
     date: Option<NaiveDate>,
 }
 
+impl GetTickerDetails {
+    pub fn new<T: Into<Ticker>>(ticker: T) -> Self {
+        Self {
+            ticker: ticker.into(),
+            date: None,
+        }
+    }
+
+    pub fn date(mut self, date: NaiveDate) -> Self {
+        self.date = Some(date);
+        self
+    }
+}
+
 impl Request for GetTickerDetails {
     type Data = Self;
     type Response = TickerDetailsWrapper;
 
     fn endpoint(&self) -> Cow<str> {
-        format!("/vX/reference/tickers/{}", self.ticker).into()
+        format!("/v3/reference/tickers/{}", self.ticker).into()
     }
 
     fn data(&self) -> RequestData<&Self> {
@@ -64,20 +114,42 @@ impl Request for GetTickerDetails {
 mod test {
     use super::*;
     use crate::rest::client_with_url;
+    use chrono::TimeZone;
     use mockito::{mock, Matcher};
 
     #[tokio::test]
     async fn get_ticker_details() {
-        let _m = mock("GET", "/vX/reference/tickers/AAPL")
+        let _m = mock("GET", "/v3/reference/tickers/AAPL")
             .match_query(Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()))
             .with_body(r#"{"count":1,"request_id":"31d59dda-80e5-4721-8496-d0d32a654afe","results":{"active":true,"address":{"address1":"One Apple Park Way","city":"Cupertino","state":"CA"},"cik":"0000320193","composite_figi":"BBG000B9XRY4","currency_name":"usd","last_updated_utc":"2020-12-27T00:00:00Z","locale":"us","market":"stocks","market_cap":2082042128180,"name":"Apple Inc.","outstanding_shares":17001800000,"phone_number":"(408) 996-1010","primary_exchange":"XNAS","share_class_figi":"BBG001S5N8V8","sic_code":"3571","sic_description":"ELECTRONIC COMPUTERS","ticker":"AAPL","type":"CS"},"status":"OK"}"#).create();
         let url = mockito::server_url();
 
         let client = client_with_url(&url, "TOKEN");
-        let req = GetTickerDetails {
-            ticker: "AAPL".to_string(),
-            date: None,
-        };
+        let req = GetTickerDetails::new("AAPL");
         client.send(&req).await.unwrap();
     }
+
+    #[tokio::test]
+    async fn get_delisted_ticker_details() {
+        let _m = mock("GET", "/v3/reference/tickers/TWTR")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()),
+                Matcher::UrlEncoded("date".into(), "2021-01-04".into()),
+            ]))
+            .with_body(r#"{"request_id":"0b6d2a9e-6c5c-4a1c-9d3d-7a4b5c6d7e8f","results":{"ticker":"TWTR","name":"Twitter, Inc.","market":"stocks","locale":"us","primary_exchange":"XNYS","type":"CS","active":false,"currency_name":"usd","cik":"0001418091","composite_figi":"BBG000H6HNW3","share_class_figi":"BBG001SGVK79","delisted_utc":"2022-11-08T05:00:00Z","description":"Twitter is a global social media platform.","homepage_url":"https://www.twitter.com","list_date":"2013-11-07","branding":{"logo_url":"https://api.polygon.io/v1/reference/company-branding/logo.svg"},"ticker_root":"TWTR","total_employees":7500,"weighted_shares_outstanding":795892000},"status":"OK"}"#)
+            .create();
+        let url = mockito::server_url();
+
+        let client = client_with_url(&url, "TOKEN");
+        let req = GetTickerDetails::new("TWTR").date(NaiveDate::from_ymd_opt(2021, 1, 4).unwrap());
+        let details = client.send(&req).await.unwrap().results;
+        assert!(!details.active);
+        assert_eq!(
+            details.delisted_utc,
+            Some(Utc.with_ymd_and_hms(2022, 11, 8, 5, 0, 0).unwrap())
+        );
+        assert_eq!(details.market_cap, None);
+        assert_eq!(details.address, None);
+        assert_eq!(details.total_employees, Some(7500));
+    }
 }
@@ -0,0 +1,187 @@
+use crate::common::Ticker;
+use crate::rest::cursor::*;
+use crate::rest::SortOrder;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use vila::pagination::{query::*, *};
+use vila::{Request, RequestData};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Publisher {
+    pub name: String,
+    pub homepage_url: Option<String>,
+    pub logo_url: Option<String>,
+    pub favicon_url: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Sentiment {
+    Positive,
+    Neutral,
+    Negative,
+}
+
+/// The sentiment of an article towards one of the tickers it mentions.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Insight {
+    pub ticker: Ticker,
+    pub sentiment: Sentiment,
+    pub sentiment_reasoning: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct NewsArticle {
+    pub id: String,
+    pub publisher: Publisher,
+    pub title: String,
+    pub author: Option<String>,
+    pub published_utc: DateTime<Utc>,
+    pub article_url: String,
+    #[serde(default)]
+    pub tickers: Vec<String>,
+    pub amp_url: Option<String>,
+    pub image_url: Option<String>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    #[serde(default)]
+    pub insights: Vec<Insight>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TickerNewsWrapper {
+    pub count: Option<usize>,
+    #[serde(default)]
+    pub results: Vec<NewsArticle>,
+    pub status: String,
+    pub request_id: String,
+    pub next_url: Option<String>,
+}
+
+impl NextUrl for TickerNewsWrapper {
+    fn next_url(&self) -> Option<&str> {
+        self.next_url.as_deref()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TickerNewsSort {
+    PublishedUtc,
+}
+
+/// Request news articles, optionally restricted to those mentioning a ticker.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct GetTickerNews {
+    ticker: Option<Ticker>,
+    published_utc: Option<DateTime<Utc>>,
+    #[serde(rename = "published_utc.gte")]
+    published_utc_gte: Option<DateTime<Utc>>,
+    #[serde(rename = "published_utc.lte")]
+    published_utc_lte: Option<DateTime<Utc>>,
+    order: Option<SortOrder>,
+    sort: Option<TickerNewsSort>,
+    limit: Option<u32>,
+}
+
+impl GetTickerNews {
+    pub fn new() -> Self {
+        Self {
+            limit: Some(1000),
+            ..Default::default()
+        }
+    }
+
+    pub fn ticker<T: Into<Ticker>>(mut self, ticker: T) -> Self {
+        self.ticker = Some(ticker.into());
+        self
+    }
+
+    pub fn published_utc(mut self, published_utc: DateTime<Utc>) -> Self {
+        self.published_utc = Some(published_utc);
+        self
+    }
+
+    pub fn published_utc_gte(mut self, published_utc: DateTime<Utc>) -> Self {
+        self.published_utc_gte = Some(published_utc);
+        self
+    }
+
+    pub fn published_utc_lte(mut self, published_utc: DateTime<Utc>) -> Self {
+        self.published_utc_lte = Some(published_utc);
+        self
+    }
+
+    pub fn order(mut self, order: SortOrder) -> Self {
+        self.order = Some(order);
+        self
+    }
+
+    pub fn sort(mut self, sort: TickerNewsSort) -> Self {
+        self.sort = Some(sort);
+        self
+    }
+
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+}
+
+impl Request for GetTickerNews {
+    type Data = Self;
+    type Response = TickerNewsWrapper;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        "/v2/reference/news".into()
+    }
+
+    fn data(&self) -> RequestData<&Self> {
+        RequestData::Query(self)
+    }
+}
+
+impl PaginatedRequest for GetTickerNews {
+    type Data = CursorPaginationData;
+    type Paginator = QueryPaginator<TickerNewsWrapper, CursorPaginationData>;
+
+    fn paginator(&self) -> Self::Paginator {
+        cursor_paginator()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rest::client_with_url;
+    use chrono::TimeZone;
+    use mockito::{mock, Matcher};
+
+    #[tokio::test]
+    async fn get_ticker_news() {
+        let _m = mock("GET", "/v2/reference/news")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()),
+                Matcher::UrlEncoded("ticker".into(), "AAPL".into()),
+                Matcher::UrlEncoded("published_utc.gte".into(), "2021-04-26T00:00:00Z".into()),
+                Matcher::UrlEncoded("order".into(), "desc".into()),
+            ]))
+            .with_body(r#"{"count":1,"next_url":"https://api.polygon.io/v2/reference/news?cursor=eyJsaW1pdCI6MSwic29ydCI6InB1Ymxpc2hlZF91dGMiLCJvcmRlciI6ImFzY2VuZGluZyIsInRpY2tlciI6e30sInB1Ymxpc2hlZF91dGMiOnsiZ3RlIjoiMjAyMS0wNC0yNiJ9LCJzZWFyY2hfYWZ0ZXIiOlsxNjE5NDA0Mzk3MDAwLG51bGxdfQ","request_id":"831afdb0b8078549fed053476984947a","results":[{"amp_url":"https://amp.benzinga.com/amp/content/20784086","article_url":"https://www.benzinga.com/markets/cryptocurrency/21/04/20784086/cathie-wood-adds-more-coinbase-skillz-trims-square","author":"Rachit  Vats","description":"Cathie Wood-led Ark Investment Management on Friday snapped up another 221,167 shares of the cryptocurrency exchange Coinbase Global Inc.","id":"nJsSJJdwViHZcw5367rZi7_qkXLfMzacXBfpv-vD9UA","image_url":"https://cdn2.benzinga.com/files/imagecache/og_image_social_share_1200x630/images/story/2012/andre-francois-mckenzie-auhr4gcqcce-unsplash.jpg?width=720","insights":[{"sentiment":"positive","sentiment_reasoning":"Cathie Wood continues to buy shares of Coinbase.","ticker":"COIN"}],"keywords":["Sector ETFs","Penny Stocks","Cryptocurrency","Small Cap"],"published_utc":"2021-04-26T02:33:17Z","publisher":{"favicon_url":"https://s3.polygon.io/public/public/assets/news/favicons/benzinga.ico","homepage_url":"https://www.benzinga.com/","logo_url":"https://s3.polygon.io/public/public/assets/news/logos/benzinga.svg","name":"Benzinga"},"tickers":["DOCU","DDD","NIU","ARKF","NVDA","SKLZ","PCAR","MASS","PSTI","SPFR","TREE","PHR","IRDM","BEAM","ARKW","ARKK","ARKG","PSTG","SQ","IONS","SYRS","COIN","AAPL"],"title":"Cathie Wood Adds More Coinbase, Skillz, Trims Square"}],"status":"OK"}"#)
+            .create();
+        let url = mockito::server_url();
+
+        let client = client_with_url(&url, "TOKEN");
+        let req = GetTickerNews::new()
+            .ticker("AAPL")
+            .published_utc_gte(Utc.with_ymd_and_hms(2021, 4, 26, 0, 0, 0).unwrap())
+            .order(SortOrder::Desc);
+        let news = client.send(&req).await.unwrap();
+        let article = &news.results[0];
+        assert_eq!(article.publisher.name, "Benzinga");
+        assert_eq!(article.insights[0].ticker, "COIN");
+        assert_eq!(article.insights[0].sentiment, Sentiment::Positive);
+        assert!(news.next_url.as_deref().and_then(cursor_from_url).is_some());
+    }
+}
@@ -1,16 +1,8 @@
+pub use crate::common::AssetClass;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use uuid::Uuid;
-use vila::Request;
-
-#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
-#[serde(rename_all = "snake_case")]
-pub enum AssetClass {
-    Stocks,
-    Options,
-    Crypto,
-    Fx,
-}
+use vila::{Request, RequestData};
 
 #[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
 #[serde(rename_all = "snake_case")]
@@ -35,17 +27,37 @@ pub struct TickerTypesWrapper {
     pub status: String,
 }
 
-#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
 pub struct GetTickerTypes {
-    asset_class: AssetClass,
-    locale: Locale,
+    asset_class: Option<AssetClass>,
+    locale: Option<Locale>,
+}
+
+impl GetTickerTypes {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn asset_class(mut self, asset_class: AssetClass) -> Self {
+        self.asset_class = Some(asset_class);
+        self
+    }
+
+    pub fn locale(mut self, locale: Locale) -> Self {
+        self.locale = Some(locale);
+        self
+    }
 }
 
 impl Request for GetTickerTypes {
-    type Data = ();
+    type Data = Self;
     type Response = TickerTypesWrapper;
 
     fn endpoint(&self) -> Cow<str> {
         "/v3/reference/tickers/types".into()
     }
+
+    fn data(&self) -> RequestData<&Self> {
+        RequestData::Query(self)
+    }
 }
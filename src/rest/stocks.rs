@@ -1,22 +1,24 @@
 use super::date_utils::*;
+use crate::common::{QuoteCondition, Tape, Ticker, TradeCondition};
 use chrono::{
     serde::{ts_milliseconds, ts_nanoseconds, ts_nanoseconds_option},
     DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc,
 };
-use chrono_tz::US::Eastern;
+use chrono_tz::{Tz, US::Eastern};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fmt;
+use tracing::warn;
 use vila::pagination::{path::*, query::*, *};
 use vila::{Request, RequestData};
 
 // Quotes
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct GetQuotes<'a> {
-    ticker: &'a str,
+pub struct GetQuotes {
+    ticker: Ticker,
     date: NaiveDate,
     #[serde(with = "ts_nanoseconds")]
     timestamp: DateTime<Utc>,
@@ -26,10 +28,10 @@ pub struct GetQuotes<'a> {
     limit: u32,
 }
 
-impl<'a> GetQuotes<'a> {
-    pub fn new(ticker: &'a str, date: NaiveDate) -> Self {
+impl GetQuotes {
+    pub fn new<T: Into<Ticker>>(ticker: T, date: NaiveDate) -> Self {
         Self {
-            ticker,
+            ticker: ticker.into(),
             date,
             timestamp: Utc.ymd(1970, 1, 1).and_hms(0, 0, 0),
             timestamp_limit: None,
@@ -87,14 +89,14 @@ pub struct Quote {
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct QuoteWrapper {
-    pub ticker: String,
+    pub ticker: Ticker,
     pub results_count: u32,
     pub db_latency: u32,
     pub success: bool,
     pub results: Vec<Quote>,
 }
 
-impl<'a> Request for GetQuotes<'a> {
+impl Request for GetQuotes {
     type Data = Self;
     type Response = QuoteWrapper;
 
@@ -119,7 +121,7 @@ impl From<QuotesPaginationData> for QueryModifier {
         QueryModifier { data }
     }
 }
-impl<'a> PaginatedRequest for GetQuotes<'a> {
+impl PaginatedRequest for GetQuotes {
     type Data = QuotesPaginationData;
     type Paginator = QueryPaginator<QuoteWrapper, QuotesPaginationData>;
 
@@ -146,6 +148,138 @@ impl<'a> PaginatedRequest for GetQuotes<'a> {
     }
 }
 
+// Trades
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GetTrades {
+    ticker: Ticker,
+    date: NaiveDate,
+    #[serde(with = "ts_nanoseconds")]
+    timestamp: DateTime<Utc>,
+    #[serde(rename = "timestampLimit", default, with = "ts_nanoseconds_option")]
+    timestamp_limit: Option<DateTime<Utc>>,
+    reverse: bool,
+    limit: u32,
+}
+
+impl GetTrades {
+    pub fn new<T: Into<Ticker>>(ticker: T, date: NaiveDate) -> Self {
+        Self {
+            ticker: ticker.into(),
+            date,
+            timestamp: Utc.timestamp_nanos(0),
+            timestamp_limit: None,
+            reverse: false,
+            limit: 5000,
+        }
+    }
+
+    pub fn timestamp(mut self, timestamp: DateTime<Utc>) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    pub fn timestamp_limit(mut self, timestamp_limit: DateTime<Utc>) -> Self {
+        self.timestamp_limit = Some(timestamp_limit);
+        self
+    }
+
+    pub fn reverse(mut self, reverse: bool) -> Self {
+        self.reverse = reverse;
+        self
+    }
+
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = limit;
+        self
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Trade {
+    #[serde(with = "ts_nanoseconds")]
+    pub t: DateTime<Utc>,
+    #[serde(default, with = "ts_nanoseconds_option")]
+    pub y: Option<DateTime<Utc>>,
+    #[serde(default, with = "ts_nanoseconds_option")]
+    pub f: Option<DateTime<Utc>>,
+    pub q: u64,
+    /// The trade ID, which is only unique per ticker and exchange.
+    pub i: String,
+    pub x: u32,
+    pub s: u32,
+    #[serde(default)]
+    pub c: Vec<u32>,
+    pub p: Decimal,
+    pub z: u8,
+    /// The ID of the trade correction, if the trade was corrected.
+    pub e: Option<u32>,
+    /// The ID of the trade reporting facility, for trades reported off-exchange.
+    pub r: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TradeWrapper {
+    pub ticker: Ticker,
+    pub results_count: u32,
+    pub db_latency: u32,
+    pub success: bool,
+    pub results: Vec<Trade>,
+}
+
+impl Request for GetTrades {
+    type Data = Self;
+    type Response = TradeWrapper;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        format!("/v2/ticks/stocks/trades/{}/{}", self.ticker, self.date).into()
+    }
+
+    fn data(&self) -> RequestData<&Self> {
+        RequestData::Query(self)
+    }
+}
+
+#[derive(Clone)]
+pub struct TradesPaginationData {
+    timestamp: i64,
+}
+
+impl From<TradesPaginationData> for QueryModifier {
+    fn from(d: TradesPaginationData) -> QueryModifier {
+        let mut data = HashMap::new();
+        data.insert("timestamp".into(), d.timestamp.to_string());
+        QueryModifier { data }
+    }
+}
+
+impl PaginatedRequest for GetTrades {
+    type Data = TradesPaginationData;
+    type Paginator = QueryPaginator<TradeWrapper, TradesPaginationData>;
+
+    fn paginator(&self) -> Self::Paginator {
+        let limit = self.limit;
+        let reverse = self.reverse;
+        QueryPaginator::new(
+            move |_: Option<&TradesPaginationData>, res: &TradeWrapper| {
+                if res.results_count != limit {
+                    return None;
+                }
+                let trade = if reverse {
+                    res.results.first()
+                } else {
+                    res.results.last()
+                };
+                trade.and_then(|t| {
+                    Some(TradesPaginationData {
+                        timestamp: t.t.timestamp_nanos_opt()?,
+                    })
+                })
+            },
+        )
+    }
+}
+
 // Aggregates
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy)]
@@ -175,7 +309,7 @@ impl fmt::Display for Timespan {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum SortOrder {
     Asc,
@@ -197,7 +331,7 @@ pub struct Aggregate {
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AggregateWrapper {
-    pub ticker: String,
+    pub ticker: Ticker,
     pub status: String,
     pub adjusted: bool,
     #[serde(rename = "queryCount")]
@@ -207,34 +341,48 @@ pub struct AggregateWrapper {
     pub request_id: String,
     #[serde(default)]
     pub results: Vec<Aggregate>,
+    /// The URL of the next page, when the results were split into pages.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_url: Option<String>,
 }
 
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 /// Request aggregate bars.
 /// Note that Polygon performs time-snapping and stretching of the `from` and `to` parameters to
 /// ensure whole bars of data are returned. In order to reduce confusion, this library performs the
 /// same time-snapping and stretching before sending the raw requests.
 ///
 /// For more details, see [this Polygon blogpost](https://polygon.io/blog/aggs-api-updates/)
+///
+/// Bars are snapped in the local time of the request's timezone, which is US Eastern unless set
+/// with [`GetAggregate::timezone`].
 pub struct GetAggregate {
     #[serde(rename = "stocksTicker")]
-    ticker: String,
+    ticker: Ticker,
     multiplier: u32,
     timespan: Timespan,
     from: NaiveDateTime,
     to: NaiveDateTime,
+    #[serde(default = "eastern")]
+    timezone: Tz,
     query: GetAggregateQuery,
 }
 
+fn eastern() -> Tz {
+    Eastern
+}
+
 impl GetAggregate {
-    pub fn new<T: ToString>(ticker: T, from: NaiveDateTime, to: NaiveDateTime) -> Self {
+    /// Request the bars between two local times in the request's timezone.
+    pub fn new<T: Into<Ticker>>(ticker: T, from: NaiveDateTime, to: NaiveDateTime) -> Self {
         let (from, to) = adjust_timeperiods(from, to, 1, Timespan::Day);
         Self {
-            ticker: ticker.to_string(),
+            ticker: ticker.into(),
             multiplier: 1,
             timespan: Timespan::Day,
             from,
             to,
+            timezone: Eastern,
             query: GetAggregateQuery {
                 unadjusted: false,
                 sort: SortOrder::Asc,
@@ -243,6 +391,42 @@ impl GetAggregate {
         }
     }
 
+    /// Request the bars from the start of `from` to the end of `to`, in the request's timezone.
+    pub fn from_dates<T: Into<Ticker>>(ticker: T, from: NaiveDate, to: NaiveDate) -> Self {
+        Self::new(
+            ticker,
+            from.and_hms_opt(0, 0, 0).unwrap(),
+            to.and_hms_opt(0, 0, 0).unwrap(),
+        )
+    }
+
+    /// Request the bars between two times, snapped in the timezone of `from`. Use
+    /// [`chrono_tz::UTC`] for UTC bars, e.g. for crypto and forex tickers.
+    pub fn from_datetimes<T: Into<Ticker>>(
+        ticker: T,
+        from: DateTime<Tz>,
+        to: DateTime<Tz>,
+    ) -> Self {
+        let timezone = from.timezone();
+        Self::new(
+            ticker,
+            from.naive_local(),
+            to.with_timezone(&timezone).naive_local(),
+        )
+        .timezone(timezone)
+    }
+
+    /// The timezone that `from` and `to` are local times in, and in which bars are snapped.
+    ///
+    /// Local times that are skipped or repeated by a DST transition are resolved so that the
+    /// request covers every instant between them: a repeated time resolves to its first
+    /// occurrence in `from` and its second in `to`, and a skipped time resolves to the
+    /// transition.
+    pub fn timezone(mut self, timezone: Tz) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
     pub fn multiplier(mut self, multiplier: u32) -> Self {
         let (from, to) = adjust_timeperiods(self.from, self.to, multiplier, self.timespan);
         self.multiplier = multiplier;
@@ -273,6 +457,12 @@ impl GetAggregate {
         self.query.limit = limit;
         self
     }
+
+    /// Whether Polygon truncated a page of this request at the limit without a `next_url`. When
+    /// paginating, the remaining bars of a truncated page are requested separately.
+    pub fn is_truncated(&self, page: &AggregateWrapper) -> bool {
+        is_truncated(page, self.query.limit)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -287,14 +477,8 @@ impl Request for GetAggregate {
     type Data = GetAggregateQuery;
 
     fn endpoint(&self) -> Cow<str> {
-        let from = Eastern
-            .from_local_datetime(&self.from)
-            .unwrap()
-            .timestamp_millis();
-        let to = Eastern
-            .from_local_datetime(&self.to)
-            .unwrap()
-            .timestamp_millis();
+        let from = resolve_local(self.timezone, self.from, Bound::Start).timestamp_millis();
+        let to = resolve_local(self.timezone, self.to, Bound::End).timestamp_millis();
         format!(
             "v2/aggs/ticker/{}/range/{}/{}/{}/{}",
             self.ticker, self.multiplier, self.timespan, from, to
@@ -307,73 +491,176 @@ impl Request for GetAggregate {
     }
 }
 
-#[derive(Clone)]
+/// Where the next page of aggregates is requested from.
+#[derive(Clone, Debug)]
+enum AggregatePage {
+    /// The `next_url` returned by Polygon with the previous page.
+    Next(String),
+    /// A window of the requested range, used when Polygon does not return a `next_url`.
+    Window {
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+    },
+}
+
+#[derive(Clone, Debug)]
 pub struct AggregatePaginationData {
+    page: AggregatePage,
+}
+
+/// Applies [`AggregatePaginationData`] to a request. A `next_url` replaces the path below the API
+/// root and the query parameters it contains, while a window replaces the `from` and `to` path
+/// segments. Either way the request keeps the host and path prefix of the client's base URL.
+pub struct AggregateModifier {
+    page: AggregatePage,
+    timezone: Tz,
+}
+
+impl RequestModifier for AggregateModifier {
+    fn modify_request(
+        &self,
+        request: &mut reqwest::Request,
+    ) -> std::result::Result<(), vila::Error> {
+        match &self.page {
+            AggregatePage::Next(next_url) => {
+                let next = url::Url::parse(next_url).map_err(|e| vila::Error::Pagination {
+                    msg: format!("Invalid next_url {}: {}", next_url, e),
+                })?;
+                let prefix = api_prefix(request.url().path()).to_string();
+                let suffix = &next.path()[api_prefix(next.path()).len()..];
+                request.url_mut().set_path(&format!("{}{}", prefix, suffix));
+                let data = next.query_pairs().into_owned().collect();
+                QueryModifier { data }.modify_request(request)
+            }
+            AggregatePage::Window { from, to } => {
+                let from = resolve_local(self.timezone, *from, Bound::Start).timestamp_millis();
+                let to = resolve_local(self.timezone, *to, Bound::End).timestamp_millis();
+                // `from` and `to` are the last segments, after any prefix of the base URL
+                let segments = request.url().path_segments().map_or(0, |s| s.count());
+                let mut data = HashMap::new();
+                data.insert(segments.saturating_sub(2), from.to_string());
+                data.insert(segments.saturating_sub(1), to.to_string());
+                PathModifier { data }.modify_request(request)
+            }
+        }
+    }
+}
+
+/// The part of an aggregates path before the API root, e.g. `/polygon` for a client whose base
+/// URL is mounted at `https://host/polygon`.
+fn api_prefix(path: &str) -> &str {
+    &path[..path.find("/v2/aggs/").unwrap_or(0)]
+}
+
+/// Follows the `next_url` of each page, falling back to windows of the requested range when
+/// Polygon does not return one.
+pub struct AggregatePaginator {
     from: NaiveDateTime,
     to: NaiveDateTime,
+    timezone: Tz,
+    multiplier: u32,
+    timespan: Timespan,
+    sort: SortOrder,
+    limit: u32,
 }
 
-impl From<AggregatePaginationData> for PathModifier {
-    fn from(d: AggregatePaginationData) -> PathModifier {
-        let from = Eastern
-            .from_local_datetime(&d.from)
-            .unwrap()
-            .timestamp_millis();
-        let to = Eastern
-            .from_local_datetime(&d.to)
-            .unwrap()
-            .timestamp_millis();
-        let mut data = HashMap::new();
-        data.insert(7, from.to_string());
-        data.insert(8, to.to_string());
-        PathModifier { data }
+impl AggregatePaginator {
+    fn window(&self, from: NaiveDateTime) -> State<AggregatePaginationData> {
+        if from > self.to {
+            return State::End;
+        }
+        let to = next_pagination_date(from, self.to, self.limit, self.multiplier, self.timespan);
+        State::Next(AggregatePaginationData {
+            page: AggregatePage::Window { from, to },
+        })
+    }
+}
+
+impl Paginator<AggregateWrapper, AggregatePaginationData> for AggregatePaginator {
+    type Modifier = AggregateModifier;
+
+    fn modifier(&self, data: AggregatePaginationData) -> AggregateModifier {
+        AggregateModifier {
+            page: data.page,
+            timezone: self.timezone,
+        }
+    }
+
+    fn next(
+        &self,
+        prev: Option<&AggregatePaginationData>,
+        res: &AggregateWrapper,
+    ) -> State<AggregatePaginationData> {
+        if let Some(next_url) = &res.next_url {
+            return State::Next(AggregatePaginationData {
+                page: AggregatePage::Next(next_url.clone()),
+            });
+        }
+        if let Some(last) = res.results.last().filter(|_| is_truncated(res, self.limit)) {
+            // Without a next_url the remaining bars of the page were dropped, so continue from
+            // the bar after the last one returned, or before it when sorted in descending order.
+            warn!(
+                ticker = %res.ticker,
+                limit = self.limit,
+                last = %last.t,
+                "Aggregates were truncated at the limit, requesting the remaining bars"
+            );
+            let start = last.t.with_timezone(&self.timezone).naive_local();
+            return match self.sort {
+                SortOrder::Asc => self.window(
+                    bar_end(start, self.multiplier, self.timespan) + Duration::milliseconds(1),
+                ),
+                SortOrder::Desc if start > self.from => State::Next(AggregatePaginationData {
+                    page: AggregatePage::Window {
+                        from: self.from,
+                        to: start - Duration::milliseconds(1),
+                    },
+                }),
+                SortOrder::Desc => State::End,
+            };
+        }
+        match prev.map(|p| &p.page) {
+            Some(AggregatePage::Window { to, .. }) if *to < self.to => {
+                self.window(*to + Duration::milliseconds(1))
+            }
+            _ => State::End,
+        }
     }
 }
 
 impl PaginatedRequest for GetAggregate {
     type Data = AggregatePaginationData;
-    type Paginator = PathPaginator<AggregateWrapper, AggregatePaginationData>;
-    fn initial_page(&self) -> Option<AggregatePaginationData> {
-        let initial_to = next_pagination_date(
-            self.from,
-            self.to,
-            self.query.limit,
-            self.multiplier,
-            self.timespan,
-        );
-        Some(AggregatePaginationData {
-            from: self.from,
-            to: initial_to,
-        })
-    }
+    type Paginator = AggregatePaginator;
+
     fn paginator(&self) -> Self::Paginator {
-        let final_to = self.to;
-        let multiplier = self.multiplier;
-        let timespan = self.timespan;
-        let limit = self.query.limit;
-        PathPaginator::new(
-            move |p: Option<&AggregatePaginationData>, _: &AggregateWrapper| match p {
-                None => unreachable!(),
-                Some(data) => {
-                    if data.to == final_to {
-                        None
-                    } else {
-                        let from = data.to + Duration::milliseconds(1);
-                        let to = next_pagination_date(from, final_to, limit, multiplier, timespan);
-                        Some(AggregatePaginationData { from, to })
-                    }
-                }
-            },
-        )
+        AggregatePaginator {
+            from: self.from,
+            to: self.to,
+            timezone: self.timezone,
+            multiplier: self.multiplier,
+            timespan: self.timespan,
+            sort: self.query.sort,
+            limit: self.query.limit,
+        }
     }
 }
 
+fn is_truncated(page: &AggregateWrapper, limit: u32) -> bool {
+    page.next_url.is_none() && page.results.len() >= limit as usize
+}
+
 // Snapshot
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct GetTickerSnapshot<'a>(pub &'a str);
+pub struct GetTickerSnapshot(pub Ticker);
 
-impl Request for GetTickerSnapshot<'_> {
+impl GetTickerSnapshot {
+    pub fn new<T: Into<Ticker>>(ticker: T) -> Self {
+        Self(ticker.into())
+    }
+}
+
+impl Request for GetTickerSnapshot {
     type Data = ();
     type Response = TickerSnapshotWrapper;
 
@@ -383,18 +670,18 @@ impl Request for GetTickerSnapshot<'_> {
 }
 
 #[non_exhaustive]
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum TickerSnapshotStatus {
     OK,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TickerSnapshotWrapper {
     pub status: TickerSnapshotStatus,
     pub ticker: TickerSnapshot,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct TickerSnapshot {
     pub day: AggregateSnapshot,
@@ -404,7 +691,7 @@ pub struct TickerSnapshot {
     pub minute: AggregateSnapshot,
     #[serde(rename = "prevDay")]
     pub previous_day: AggregateSnapshot,
-    pub ticker: String,
+    pub ticker: Ticker,
     pub todays_change: Decimal,
     #[serde(rename = "todaysChangePerc")]
     pub todays_change_percent: Decimal,
@@ -412,7 +699,7 @@ pub struct TickerSnapshot {
     pub updated: DateTime<Utc>,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AggregateSnapshot {
     pub av: Option<Decimal>,
     pub o: Decimal,
@@ -423,7 +710,7 @@ pub struct AggregateSnapshot {
     pub vw: Decimal,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct QuoteSnapshot {
     #[serde(rename = "p")]
     pub bid_price: Decimal,
@@ -437,10 +724,12 @@ pub struct QuoteSnapshot {
     pub t: DateTime<Utc>,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TradeSnapshot {
-    // TODO: Implement with TradeCondition from ws.
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    /// The condition codes of the trade, which can be resolved with a
+    /// [`ConditionRegistry`](super::ConditionRegistry). They are kept as codes so that
+    /// conditions added by Polygon don't fail the snapshot.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub c: Option<Vec<u8>>,
     pub i: String,
     pub p: Decimal,
@@ -450,15 +739,147 @@ pub struct TradeSnapshot {
     pub x: u8,
 }
 
+// Last trade and quote
+
+/// Request the most recent trade for a ticker.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GetLastTrade(pub Ticker);
+
+impl GetLastTrade {
+    pub fn new<T: Into<Ticker>>(ticker: T) -> Self {
+        Self(ticker.into())
+    }
+}
+
+impl Request for GetLastTrade {
+    type Data = ();
+    type Response = LastTradeWrapper;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        format!("/v2/last/trade/{}", self.0).into()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LastTradeWrapper {
+    pub request_id: String,
+    pub status: String,
+    pub results: LastTrade,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LastTrade {
+    #[serde(rename = "T")]
+    pub ticker: Ticker,
+    #[serde(rename = "i")]
+    pub trade_id: String,
+    #[serde(rename = "x")]
+    pub exchange_id: u8,
+    #[serde(rename = "z")]
+    pub tape: Tape,
+    #[serde(rename = "p")]
+    pub price: Decimal,
+    #[serde(rename = "s")]
+    pub size: u32,
+    #[serde(rename = "c", default)]
+    pub conditions: Vec<TradeCondition>,
+    /// The ID of the trade correction, if the trade was corrected.
+    #[serde(rename = "e")]
+    pub correction: Option<u32>,
+    /// The ID of the trade reporting facility, for trades reported off-exchange.
+    #[serde(rename = "r")]
+    pub trf_id: Option<u32>,
+    #[serde(rename = "q")]
+    pub sequence_number: u64,
+    #[serde(rename = "t", with = "ts_nanoseconds")]
+    pub sip_timestamp: DateTime<Utc>,
+    #[serde(rename = "y", default, with = "ts_nanoseconds_option")]
+    pub participant_timestamp: Option<DateTime<Utc>>,
+    #[serde(rename = "f", default, with = "ts_nanoseconds_option")]
+    pub trf_timestamp: Option<DateTime<Utc>>,
+}
+
+/// Request the most recent NBBO quote for a ticker.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GetLastQuote(pub Ticker);
+
+impl GetLastQuote {
+    pub fn new<T: Into<Ticker>>(ticker: T) -> Self {
+        Self(ticker.into())
+    }
+}
+
+impl Request for GetLastQuote {
+    type Data = ();
+    type Response = LastQuoteWrapper;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        format!("/v2/last/nbbo/{}", self.0).into()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LastQuoteWrapper {
+    pub request_id: String,
+    pub status: String,
+    pub results: LastQuote,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LastQuote {
+    #[serde(rename = "T")]
+    pub ticker: Ticker,
+    #[serde(rename = "p")]
+    pub bid_price: Decimal,
+    #[serde(rename = "s")]
+    pub bid_size: u32,
+    #[serde(rename = "x")]
+    pub bid_exchange: u8,
+    #[serde(rename = "P")]
+    pub ask_price: Decimal,
+    #[serde(rename = "S")]
+    pub ask_size: u32,
+    #[serde(rename = "X")]
+    pub ask_exchange: u8,
+    #[serde(rename = "z")]
+    pub tape: Tape,
+    #[serde(rename = "c", default)]
+    pub conditions: Vec<QuoteCondition>,
+    #[serde(rename = "i", default)]
+    pub indicators: Vec<u32>,
+    #[serde(rename = "q")]
+    pub sequence_number: u64,
+    #[serde(rename = "t", with = "ts_nanoseconds")]
+    pub sip_timestamp: DateTime<Utc>,
+    #[serde(rename = "y", default, with = "ts_nanoseconds_option")]
+    pub participant_timestamp: Option<DateTime<Utc>>,
+    #[serde(rename = "f", default, with = "ts_nanoseconds_option")]
+    pub trf_timestamp: Option<DateTime<Utc>>,
+}
+
 // Previous close
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct GetPreviousClose<'a> {
-    pub ticker: &'a str,
+pub struct GetPreviousClose {
+    pub ticker: Ticker,
     pub unadjusted: bool,
 }
 
-impl Request for GetPreviousClose<'_> {
+impl GetPreviousClose {
+    pub fn new<T: Into<Ticker>>(ticker: T) -> Self {
+        Self {
+            ticker: ticker.into(),
+            unadjusted: false,
+        }
+    }
+
+    pub fn unadjusted(mut self, unadjusted: bool) -> Self {
+        self.unadjusted = unadjusted;
+        self
+    }
+}
+
+impl Request for GetPreviousClose {
     type Data = Self;
     type Response = PreviousCloseWrapper;
 
@@ -473,7 +894,7 @@ impl Request for GetPreviousClose<'_> {
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct PreviousCloseWrapper {
-    pub ticker: String,
+    pub ticker: Ticker,
     pub status: String,
     pub adjusted: bool,
     #[serde(rename = "queryCount")]
@@ -484,7 +905,7 @@ pub struct PreviousCloseWrapper {
     pub results: Vec<PreviousClose>,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PreviousClose {
     pub o: Decimal,
     pub h: Decimal,
@@ -528,6 +949,199 @@ mod test {
         client.send(&req).await.unwrap();
     }
 
+    #[test]
+    fn aggregate_timezones() {
+        let from = chrono_tz::UTC.ymd(2021, 3, 1).and_hms(0, 0, 0);
+        let req = GetAggregate::from_datetimes("X:BTCUSD", from, from);
+        assert_eq!(
+            req.endpoint(),
+            "v2/aggs/ticker/X:BTCUSD/range/1/day/1614556800000/1614643199999"
+        );
+
+        // Clocks in São Paulo sprang forward from midnight to 01:00 on 2018-11-04, so the day
+        // starts at the transition
+        let sao_paulo: Tz = "America/Sao_Paulo".parse().unwrap();
+        let day = NaiveDate::from_ymd(2018, 11, 4);
+        let req = GetAggregate::from_dates("PBR", day, day).timezone(sao_paulo);
+        assert_eq!(
+            req.endpoint(),
+            "v2/aggs/ticker/PBR/range/1/day/1541300400000/1541383199999"
+        );
+
+        // and fell back from midnight to 23:00 on 2019-02-16, so the day ends after the second
+        // 23:59
+        let day = NaiveDate::from_ymd(2019, 2, 16);
+        let req = GetAggregate::from_dates("PBR", day, day).timezone(sao_paulo);
+        assert_eq!(
+            req.endpoint(),
+            "v2/aggs/ticker/PBR/range/1/day/1550282400000/1550372399999"
+        );
+    }
+
+    fn aggregate_page(t: i64, next_url: Option<String>) -> String {
+        let next_url = next_url
+            .map(|url| format!(r#","next_url":"{}""#, url))
+            .unwrap_or_default();
+        format!(
+            r#"{{"ticker":"AAPL","status":"OK","queryCount":1,"resultsCount":1,"adjusted":true,"results":[{{"v":100,"o":1,"c":1,"h":1,"l":1,"t":{}}}],"request_id":"a1"{}}}"#,
+            t, next_url
+        )
+    }
+
+    #[tokio::test]
+    async fn get_aggregate_follows_next_url() {
+        use futures::TryStreamExt;
+        let url = mockito::server_url();
+        let next_url = format!(
+            "{}/v2/aggs/ticker/AAPL/range/1/day/1614661200000/1614747599999?cursor=bGltaXQ9MQ",
+            url
+        );
+        let _first = mock(
+            "GET",
+            "/v2/aggs/ticker/AAPL/range/1/day/1614574800000/1614747599999",
+        )
+        .match_query(Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()))
+        .with_body(aggregate_page(1614574800000, Some(next_url)))
+        .create();
+        let _second = mock(
+            "GET",
+            "/v2/aggs/ticker/AAPL/range/1/day/1614661200000/1614747599999",
+        )
+        .match_query(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()),
+            Matcher::UrlEncoded("cursor".into(), "bGltaXQ9MQ".into()),
+        ]))
+        .with_body(aggregate_page(1614661200000, None))
+        .create();
+
+        let client = client_with_url(&url, "TOKEN");
+        let req = GetAggregate::new(
+            "AAPL",
+            NaiveDate::from_ymd(2021, 3, 1).and_hms(0, 0, 0),
+            NaiveDate::from_ymd(2021, 3, 2).and_hms(0, 0, 0),
+        )
+        .limit(1);
+        let pages: Vec<AggregateWrapper> = client.send_paginated(&req).try_collect().await.unwrap();
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[1].results[0].t.timestamp_millis(), 1614661200000);
+    }
+
+    #[tokio::test]
+    async fn get_aggregate_keeps_base_url_prefix() {
+        use futures::TryStreamExt;
+        let next_url = "https://api.polygon.io/v2/aggs/ticker/AMD/range/1/day/1614661200000/1614833999999?cursor=YW1k";
+        let _first = mock(
+            "GET",
+            "/polygon/v2/aggs/ticker/AMD/range/1/day/1614574800000/1614833999999",
+        )
+        .match_query(Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()))
+        .with_body(aggregate_page(1614574800000, Some(next_url.into())))
+        .create();
+        let _second = mock(
+            "GET",
+            "/polygon/v2/aggs/ticker/AMD/range/1/day/1614661200000/1614833999999",
+        )
+        .match_query(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()),
+            Matcher::UrlEncoded("cursor".into(), "YW1k".into()),
+        ]))
+        .with_body(aggregate_page(1614661200000, None))
+        .create();
+        // The second page is truncated, so the rest of the range is requested as a window
+        let _third = mock(
+            "GET",
+            Matcher::Regex(r"^/polygon/v2/aggs/ticker/AMD/range/1/day/1614747600000/\d+$".into()),
+        )
+        .match_query(Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()))
+        .with_body(aggregate_page(1614747600000, None))
+        .create();
+
+        let url = format!("{}/polygon", mockito::server_url());
+        let client = client_with_url(&url, "TOKEN");
+        let req = GetAggregate::new(
+            "AMD",
+            NaiveDate::from_ymd(2021, 3, 1).and_hms(0, 0, 0),
+            NaiveDate::from_ymd(2021, 3, 3).and_hms(0, 0, 0),
+        )
+        .limit(1);
+        let pages: Vec<AggregateWrapper> = client.send_paginated(&req).try_collect().await.unwrap();
+        let stamps: Vec<i64> = pages
+            .iter()
+            .map(|p| p.results[0].t.timestamp_millis())
+            .collect();
+        assert_eq!(stamps, vec![1614574800000, 1614661200000, 1614747600000]);
+    }
+
+    #[tokio::test]
+    async fn get_aggregate_recovers_truncated_pages() {
+        use futures::TryStreamExt;
+        let _first = mock(
+            "GET",
+            "/v2/aggs/ticker/AAPL/range/1/day/1614574800000/1614747599999",
+        )
+        .match_query(Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()))
+        .with_body(aggregate_page(1614574800000, None))
+        .create();
+        let _second = mock(
+            "GET",
+            "/v2/aggs/ticker/AAPL/range/1/day/1614661200000/1614747599999",
+        )
+        .match_query(Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()))
+        .with_body(aggregate_page(1614661200000, None))
+        .create();
+
+        let client = client_with_url(&mockito::server_url(), "TOKEN");
+        let req = GetAggregate::new(
+            "AAPL",
+            NaiveDate::from_ymd(2021, 3, 1).and_hms(0, 0, 0),
+            NaiveDate::from_ymd(2021, 3, 2).and_hms(0, 0, 0),
+        )
+        .limit(1);
+        let pages: Vec<AggregateWrapper> = client.send_paginated(&req).try_collect().await.unwrap();
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[1].results[0].t.timestamp_millis(), 1614661200000);
+        assert!(pages.iter().all(|p| req.is_truncated(p)));
+    }
+
+    #[tokio::test]
+    async fn get_aggregate_recovers_truncated_desc_pages() {
+        use futures::TryStreamExt;
+        let desc = Matcher::AllOf(vec![
+            Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()),
+            Matcher::UrlEncoded("sort".into(), "desc".into()),
+        ]);
+        let _first = mock(
+            "GET",
+            "/v2/aggs/ticker/MSFT/range/1/day/1614574800000/1614747599999",
+        )
+        .match_query(desc.clone())
+        .with_body(aggregate_page(1614661200000, None))
+        .create();
+        let _second = mock(
+            "GET",
+            "/v2/aggs/ticker/MSFT/range/1/day/1614574800000/1614661199999",
+        )
+        .match_query(desc)
+        .with_body(aggregate_page(1614574800000, None))
+        .create();
+
+        let client = client_with_url(&mockito::server_url(), "TOKEN");
+        let req = GetAggregate::new(
+            "MSFT",
+            NaiveDate::from_ymd(2021, 3, 1).and_hms(0, 0, 0),
+            NaiveDate::from_ymd(2021, 3, 2).and_hms(0, 0, 0),
+        )
+        .sort(SortOrder::Desc)
+        .limit(1);
+        let pages: Vec<AggregateWrapper> = client.send_paginated(&req).try_collect().await.unwrap();
+        let stamps: Vec<i64> = pages
+            .iter()
+            .map(|p| p.results[0].t.timestamp_millis())
+            .collect();
+        assert_eq!(stamps, vec![1614661200000, 1614574800000]);
+        assert!(pages.iter().all(|p| req.is_truncated(p)));
+    }
+
     #[tokio::test]
     async fn get_quotes() {
         let _m = mock("GET", "/v2/ticks/stocks/nbbo/AAPL/2021-03-01")
@@ -541,6 +1155,27 @@ mod test {
         client.send(&req).await.unwrap();
     }
 
+    #[tokio::test]
+    async fn send_persisted_request() {
+        let _m = mock("GET", "/v2/ticks/stocks/nbbo/MSFT/2021-03-01")
+            .match_query(Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()))
+            .with_body(r#"{"ticker":"MSFT","success":true,"results_count":1,"db_latency":43,"results":[{"t":1517562000065700400,"y":1517562000065321200,"q":2060,"c":[1],"z":3,"p":102.7,"s":60,"x":11,"P":0,"S":0,"X":0}]}"#).create();
+
+        let ticker = String::from("MSFT");
+        let queued =
+            serde_json::to_string(&GetQuotes::new(&ticker, NaiveDate::from_ymd(2021, 3, 1)))
+                .unwrap();
+        drop(ticker);
+        let req: GetQuotes = serde_json::from_str(&queued).unwrap();
+
+        let client = client_with_url(&mockito::server_url(), "TOKEN");
+        let res = tokio::spawn(async move { client.send(&req).await })
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(res.ticker, "MSFT");
+    }
+
     //#[tokio::test]
     //async fn get_quotes_paginated() {
     //    use futures::StreamExt;
@@ -561,13 +1196,65 @@ mod test {
     async fn get_ticker_snapshot() {
         let _m = mock("GET", "/v2/snapshot/locale/us/markets/stocks/tickers/AAPL")
             .match_query(Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()))
-            .with_body(r#"{"status":"OK","ticker":{"day":{"c":120.4229,"h":120.53,"l":118.81,"o":119.62,"v":28727868,"vw":119.725},"lastQuote":{"P":120.47,"S":4,"p":120.46,"s":8,"t":1605195918507251700},"lastTrade":{"c":null,"i":"4046","p":120.47,"s":236,"t":1605195918306274000,"x":10},"min":{"av":28724441,"c":120.4201,"h":120.468,"l":120.37,"o":120.435,"v":270796,"vw":120.4129},"prevDay":{"c":119.49,"h":119.63,"l":116.44,"o":117.19,"v":110597265,"vw":118.4998},"ticker":"AAPL","todaysChange":0.98,"todaysChangePerc":0.82,"updated":1605195918306274000}}"#).create();
+            .with_body(r#"{"status":"OK","ticker":{"day":{"c":120.4229,"h":120.53,"l":118.81,"o":119.62,"v":28727868,"vw":119.725},"lastQuote":{"P":120.47,"S":4,"p":120.46,"s":8,"t":1605195918507251700},"lastTrade":{"c":[14,99],"i":"4046","p":120.47,"s":236,"t":1605195918306274000,"x":10},"min":{"av":28724441,"c":120.4201,"h":120.468,"l":120.37,"o":120.435,"v":270796,"vw":120.4129},"prevDay":{"c":119.49,"h":119.63,"l":116.44,"o":117.19,"v":110597265,"vw":118.4998},"ticker":"AAPL","todaysChange":0.98,"todaysChangePerc":0.82,"updated":1605195918306274000}}"#).create();
 
         let url = mockito::server_url();
 
         let client = client_with_url(&url, "TOKEN");
-        let req = GetTickerSnapshot("AAPL");
-        client.send(&req).await.unwrap();
+        let req = GetTickerSnapshot::new("AAPL");
+        let snapshot = client.send(&req).await.unwrap().ticker;
+        assert_eq!(snapshot.last_trade.c, Some(vec![14, 99]));
+    }
+
+    #[tokio::test]
+    async fn get_last_trade() {
+        let _m = mock("GET", "/v2/last/trade/AAPL")
+            .match_query(Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()))
+            .with_body(r#"{"request_id":"f05562305bd26ced64b98ed68b3c5d96","results":{"T":"AAPL","c":[37],"f":1617901342969796400,"i":"118749","p":129.8473,"q":3135876,"r":202,"s":25,"t":1617901342969834000,"x":4,"y":1617901342968000000,"z":3},"status":"OK"}"#)
+            .create();
+        let url = mockito::server_url();
+
+        let client = client_with_url(&url, "TOKEN");
+        let trade = client
+            .send(&GetLastTrade::new("AAPL"))
+            .await
+            .unwrap()
+            .results;
+        assert_eq!(trade.tape, Tape::C);
+        assert_eq!(trade.conditions, vec![TradeCondition::OddLotTrade]);
+        assert_eq!(trade.trf_id, Some(202));
+        assert_eq!(
+            trade.sip_timestamp.timestamp_nanos_opt(),
+            Some(1617901342969834000)
+        );
+        assert_eq!(
+            trade.trf_timestamp.unwrap().timestamp_subsec_nanos(),
+            969796400
+        );
+    }
+
+    #[tokio::test]
+    async fn get_last_quote() {
+        let _m = mock("GET", "/v2/last/nbbo/AAPL")
+            .match_query(Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()))
+            .with_body(r#"{"request_id":"b84e24636301f19f88e0dfbf9a45ed5c","results":{"P":127.98,"S":7,"T":"AAPL","X":19,"c":[1],"i":[604],"p":127.96,"q":83480742,"s":1,"t":1617827221349730300,"x":11,"y":1617827221349366000,"z":3},"status":"OK"}"#)
+            .create();
+        let url = mockito::server_url();
+
+        let client = client_with_url(&url, "TOKEN");
+        let quote = client
+            .send(&GetLastQuote::new("AAPL"))
+            .await
+            .unwrap()
+            .results;
+        assert_eq!(quote.bid_exchange, 11);
+        assert_eq!(quote.ask_exchange, 19);
+        assert_eq!(quote.conditions, vec![QuoteCondition::RegularTwoSidedOpen]);
+        assert!(quote.trf_timestamp.is_none());
+        assert_eq!(
+            quote.participant_timestamp.unwrap().timestamp_nanos_opt(),
+            Some(1617827221349366000)
+        );
     }
 
     #[tokio::test]
@@ -582,10 +1269,7 @@ mod test {
         let url = mockito::server_url();
 
         let client = client_with_url(&url, "TOKEN");
-        let req = GetPreviousClose {
-            ticker: "AAPL",
-            unadjusted: false,
-        };
+        let req = GetPreviousClose::new("AAPL");
         client.send(&req).await.unwrap();
     }
 }
@@ -0,0 +1,450 @@
+use super::http::format_request;
+use super::{ResponseCache, RetryPolicy};
+use crate::errors::{Error, Result};
+use futures::prelude::*;
+use reqwest::StatusCode;
+use std::borrow::Borrow;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::time::{sleep, Instant};
+use tracing::warn;
+use vila::pagination::{PaginatedRequest, Paginator, RequestModifier, State};
+use vila::Request;
+
+/// A snapshot of the calls made through a [`ThrottledClient`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ClientMetrics {
+    /// Requests sent to Polygon, including retries.
+    pub requests: u64,
+    /// Requests that had to wait for the rate limiter before being sent.
+    pub throttled: u64,
+    /// Requests that were retried.
+    pub retried: u64,
+    /// Responses with a 429 status.
+    pub rate_limited: u64,
+    /// Requests served from the response cache without being sent.
+    pub cache_hits: u64,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    requests: AtomicU64,
+    throttled: AtomicU64,
+    retried: AtomicU64,
+    rate_limited: AtomicU64,
+    cache_hits: AtomicU64,
+}
+
+impl Counters {
+    fn incr(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> ClientMetrics {
+        ClientMetrics {
+            requests: self.requests.load(Ordering::Relaxed),
+            throttled: self.throttled.load(Ordering::Relaxed),
+            retried: self.retried.load(Ordering::Relaxed),
+            rate_limited: self.rate_limited.load(Ordering::Relaxed),
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// A token bucket that allows bursts of up to `capacity` requests, refilled at a constant rate.
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    per_second: f64,
+    state: Mutex<(f64, Instant)>,
+}
+
+impl TokenBucket {
+    fn new(requests: u32, per: Duration) -> Self {
+        let capacity = f64::from(requests);
+        Self {
+            capacity,
+            per_second: capacity / per.as_secs_f64(),
+            state: Mutex::new((capacity, Instant::now())),
+        }
+    }
+
+    /// Wait for a token to become available, returning whether the caller had to wait.
+    async fn acquire(&self) -> bool {
+        let mut waited = false;
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                let (tokens, last) = &mut *state;
+                let now = Instant::now();
+                let refill = now.duration_since(*last).as_secs_f64() * self.per_second;
+                *tokens = (*tokens + refill).min(self.capacity);
+                *last = now;
+                if *tokens >= 1.0 {
+                    *tokens -= 1.0;
+                    return waited;
+                }
+                Duration::from_secs_f64((1.0 - *tokens) / self.per_second)
+            };
+            waited = true;
+            sleep(wait).await;
+        }
+    }
+}
+
+pub struct ThrottledClientBuilder {
+    base_url: String,
+    token: String,
+    rate_limit: Option<(u32, Duration)>,
+    retry_policy: RetryPolicy,
+    endpoint_limits: Vec<(String, usize)>,
+    cache: Option<ResponseCache>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+}
+
+impl ThrottledClientBuilder {
+    pub fn base_url<T: ToString>(mut self, base_url: T) -> Self {
+        self.base_url = base_url.to_string();
+        self
+    }
+
+    /// Allow at most `requests` requests in every window of length `per`, shared between all
+    /// clones of the client. Building the client fails if `requests` is zero.
+    pub fn rate_limit(mut self, requests: u32, per: Duration) -> Self {
+        self.rate_limit = Some((requests, per));
+        self
+    }
+
+    pub fn requests_per_minute(self, requests: u32) -> Self {
+        self.rate_limit(requests, Duration::from_secs(60))
+    }
+
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Allow at most `limit` requests in flight at once to endpoints starting with `prefix`, e.g.
+    /// `"/v2/aggs"`. When several prefixes match, the first one registered is used. Building the
+    /// client fails if `limit` is zero.
+    pub fn endpoint_concurrency<T: ToString>(mut self, prefix: T, limit: usize) -> Self {
+        self.endpoint_limits.push((prefix.to_string(), limit));
+        self
+    }
+
+    /// The timeout for each attempt of a request, from connecting until the body has been read.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Serve responses from, and store responses in, an on-disk cache.
+    pub fn cache(mut self, cache: ResponseCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    pub fn build(self) -> Result<ThrottledClient> {
+        if matches!(self.rate_limit, Some((0, _))) {
+            return Err(Error::InvalidClient(
+                "the rate limit must allow at least one request",
+            ));
+        }
+        if self.endpoint_limits.iter().any(|(_, limit)| *limit == 0) {
+            return Err(Error::InvalidClient(
+                "endpoint concurrency limits must allow at least one request",
+            ));
+        }
+        let mut http = reqwest::Client::builder();
+        if let Some(timeout) = self.timeout {
+            http = http.timeout(timeout);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            http = http.connect_timeout(timeout);
+        }
+        Ok(ThrottledClient {
+            inner: Arc::new(Inner {
+                http: http.build().expect("Failed to initialize the HTTP client"),
+                base_url: self.base_url,
+                token: self.token,
+                limiter: self.rate_limit.map(|(n, per)| TokenBucket::new(n, per)),
+                retry_policy: self.retry_policy,
+                endpoint_limits: self
+                    .endpoint_limits
+                    .into_iter()
+                    .map(|(prefix, limit)| {
+                        (prefix.trim_matches('/').to_string(), Semaphore::new(limit))
+                    })
+                    .collect(),
+                counters: Default::default(),
+                cache: self.cache,
+            }),
+        })
+    }
+}
+
+struct Inner {
+    http: reqwest::Client,
+    base_url: String,
+    token: String,
+    limiter: Option<TokenBucket>,
+    retry_policy: RetryPolicy,
+    endpoint_limits: Vec<(String, Semaphore)>,
+    counters: Counters,
+    cache: Option<ResponseCache>,
+}
+
+/// A REST client that rate limits requests, caps the number of concurrent requests per endpoint
+/// and retries failed requests. Clones share the same limits and metrics, so a single client can
+/// be cloned into every task that talks to Polygon.
+#[derive(Clone)]
+pub struct ThrottledClient {
+    inner: Arc<Inner>,
+}
+
+impl ThrottledClient {
+    pub fn builder<T: ToString>(token: T) -> ThrottledClientBuilder {
+        ThrottledClientBuilder {
+            base_url: "https://api.polygon.io".into(),
+            token: token.to_string(),
+            rate_limit: None,
+            retry_policy: Default::default(),
+            endpoint_limits: Vec::new(),
+            cache: None,
+            timeout: None,
+            connect_timeout: None,
+        }
+    }
+
+    pub fn metrics(&self) -> ClientMetrics {
+        self.inner.counters.snapshot()
+    }
+
+    fn format_request<R: Request>(&self, request: &R) -> Result<reqwest::Request> {
+        format_request(&self.inner.base_url, &self.inner.token, request)
+    }
+
+    fn semaphore(&self, endpoint: &str) -> Option<&Semaphore> {
+        let endpoint = endpoint.trim_matches('/');
+        self.inner
+            .endpoint_limits
+            .iter()
+            .find(|(prefix, _)| endpoint.starts_with(prefix.as_str()))
+            .map(|(_, semaphore)| semaphore)
+    }
+
+    async fn execute<T>(&self, endpoint: &str, req: reqwest::Request) -> Result<T>
+    where
+        T: for<'de> serde::Deserialize<'de>,
+    {
+        let cache = self.inner.cache.as_ref();
+        if let Some(cache) = cache {
+            if let Some(msg) = cache.get(req.url()).await? {
+                Counters::incr(&self.inner.counters.cache_hits);
+                return serde_json::from_str(&msg).map_err(|error| Error::from_body(error, msg));
+            }
+        }
+        let msg = self.fetch(endpoint, &req).await?;
+        let res =
+            serde_json::from_str(&msg).map_err(|error| Error::from_body(error, msg.clone()))?;
+        if let Some(cache) = cache {
+            cache.put(req.url(), &msg).await;
+        }
+        Ok(res)
+    }
+
+    /// Send a request, retrying it according to the retry policy, and return the response body.
+    async fn fetch(&self, endpoint: &str, req: &reqwest::Request) -> Result<String> {
+        let _permit = match self.semaphore(endpoint) {
+            Some(semaphore) => Some(semaphore.acquire().await.unwrap()),
+            None => None,
+        };
+        let counters = &self.inner.counters;
+        let policy = &self.inner.retry_policy;
+        let mut attempt = 0;
+        loop {
+            if let Some(limiter) = &self.inner.limiter {
+                if limiter.acquire().await {
+                    Counters::incr(&counters.throttled);
+                }
+            }
+            let attempt_req = req
+                .try_clone()
+                .expect("Requests with streaming bodies cannot be retried");
+            Counters::incr(&counters.requests);
+            let delay = match self.inner.http.execute(attempt_req).await {
+                Ok(res) => {
+                    let status = res.status();
+                    if status.is_success() {
+                        return res.text().await.map_err(Error::transport);
+                    }
+                    if status == StatusCode::TOO_MANY_REQUESTS {
+                        Counters::incr(&counters.rate_limited);
+                    }
+                    match policy.retry_response(attempt, status, res.headers()) {
+                        Some(delay) => delay,
+                        None => {
+                            let msg = res.text().await.unwrap_or_default();
+                            return Err(Error::from_response(status, msg));
+                        }
+                    }
+                }
+                Err(e) => match policy.retry_error(attempt, &e) {
+                    Some(delay) => delay,
+                    None => return Err(Error::transport(e)),
+                },
+            };
+            warn!(endpoint, attempt, ?delay, "Retrying request");
+            Counters::incr(&counters.retried);
+            attempt += 1;
+            sleep(delay).await;
+        }
+    }
+
+    /// Send a single `Request`
+    pub async fn send<R: Request>(&self, request: &R) -> Result<R::Response> {
+        let req = self.format_request(request)?;
+        self.execute(&request.endpoint(), req).await
+    }
+
+    /// Send a paginated request, returning a stream of results
+    pub fn send_paginated<'a, R: PaginatedRequest>(
+        &'a self,
+        request: &'a R,
+    ) -> impl Stream<Item = Result<R::Response>> + Unpin + 'a {
+        self.paginate::<R, _>(request)
+    }
+
+    /// Like [`ThrottledClient::send_paginated`], but the stream owns the request.
+    pub fn into_paginated<'a, R: PaginatedRequest + 'a>(
+        &'a self,
+        request: R,
+    ) -> impl Stream<Item = Result<R::Response>> + Unpin + 'a {
+        self.paginate::<R, _>(request)
+    }
+
+    fn paginate<'a, R, Q>(
+        &'a self,
+        request: Q,
+    ) -> impl Stream<Item = Result<R::Response>> + Unpin + 'a
+    where
+        R: PaginatedRequest + 'a,
+        Q: Borrow<R> + 'a,
+    {
+        let paginator = request.borrow().paginator();
+        let state = State::Start(request.borrow().initial_page());
+        Box::pin(stream::try_unfold(
+            (request, paginator, state),
+            move |(request, paginator, state)| async move {
+                let page = match state {
+                    State::Start(None) => None,
+                    State::Start(Some(ref page)) | State::Next(ref page) => Some(page),
+                    State::End => return Ok(None),
+                };
+                let req_data = request.borrow();
+                let mut req = self.format_request(req_data)?;
+                if let Some(page) = page {
+                    paginator
+                        .modifier(page.clone())
+                        .modify_request(&mut req)
+                        .map_err(Error::from_vila)?;
+                }
+                let response: R::Response = self.execute(&req_data.endpoint(), req).await?;
+                let state = paginator.next(page, &response);
+                Ok(Some((response, (request, paginator, state))))
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rest::{GetLastTrade, GetTickerNews};
+    use futures::TryStreamExt;
+    use mockito::{mock, Matcher};
+
+    #[tokio::test(start_paused = true)]
+    async fn token_bucket() {
+        let bucket = TokenBucket::new(2, Duration::from_secs(1));
+        let start = Instant::now();
+        assert!(!bucket.acquire().await);
+        assert!(!bucket.acquire().await);
+        assert!(bucket.acquire().await);
+        assert_eq!(start.elapsed(), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn rejects_zero_limits() {
+        let res = ThrottledClient::builder("TOKEN")
+            .requests_per_minute(0)
+            .build();
+        assert!(matches!(res, Err(Error::InvalidClient(_))));
+        let res = ThrottledClient::builder("TOKEN")
+            .endpoint_concurrency("/v2/aggs", 0)
+            .build();
+        assert!(matches!(res, Err(Error::InvalidClient(_))));
+    }
+
+    #[tokio::test]
+    async fn retries_rate_limited_requests() {
+        let _limited = mock("GET", "/v2/last/trade/MSFT")
+            .match_query(Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()))
+            .with_status(429)
+            .with_header("Retry-After", "0")
+            .with_body(r#"{"status":"ERROR","request_id":"2d4f8c1a","error":"You've exceeded the maximum requests per minute."}"#)
+            .create();
+        let _ok = mock("GET", "/v2/last/trade/MSFT")
+            .match_query(Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()))
+            .with_body(r#"{"request_id":"f05562305bd26ced64b98ed68b3c5d96","results":{"T":"MSFT","i":"118749","p":129.8473,"q":3135876,"s":25,"t":1617901342969834000,"x":4,"z":3},"status":"OK"}"#)
+            .create();
+
+        let client = ThrottledClient::builder("TOKEN")
+            .base_url(mockito::server_url())
+            .requests_per_minute(100)
+            .endpoint_concurrency("/v2/last", 1)
+            .build()
+            .unwrap();
+        let trade = client.send(&GetLastTrade::new("MSFT")).await.unwrap();
+        assert_eq!(trade.results.ticker, "MSFT");
+        assert_eq!(
+            client.metrics(),
+            ClientMetrics {
+                requests: 2,
+                throttled: 0,
+                retried: 1,
+                rate_limited: 1,
+                cache_hits: 0,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_client_errors() {
+        let _m = mock("GET", "/v2/reference/news")
+            .match_query(Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()))
+            .with_status(403)
+            .with_body(r#"{"status":"NOT_AUTHORIZED","request_id":"a1b2c3","message":"You are not entitled to this data."}"#)
+            .create();
+
+        let client = ThrottledClient::builder("TOKEN")
+            .base_url(mockito::server_url())
+            .build()
+            .unwrap();
+        let req = GetTickerNews::new().ticker("TSLA");
+        let res: Result<Vec<_>> = client.send_paginated(&req).try_collect().await;
+        assert!(matches!(
+            res,
+            Err(Error::NotEntitled { request_id: Some(id), .. }) if id == "a1b2c3"
+        ));
+        assert_eq!(client.metrics().retried, 0);
+    }
+}
@@ -0,0 +1,419 @@
+use super::{GetQuotes, GetTrades, Quote, QuoteWrapper, ThrottledClient, Trade, TradeWrapper};
+use crate::common::Ticker;
+use crate::errors::Result;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc, Weekday};
+use chrono_tz::US::Eastern;
+use futures::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use vila::pagination::PaginatedRequest;
+
+/// Tick-level requests that cover a single trading day, which can be combined over a range of
+/// days with [`GetTickRange`].
+pub trait TickRequest: PaginatedRequest {
+    type Tick;
+
+    /// The request for the ticks of `ticker` on `date`, starting no earlier than `from` and
+    /// ending no later than `limit_to`, if given.
+    fn for_day(
+        ticker: &Ticker,
+        date: NaiveDate,
+        from: DateTime<Utc>,
+        limit_to: Option<DateTime<Utc>>,
+        limit: u32,
+    ) -> Self;
+
+    fn into_ticks(response: Self::Response) -> Vec<Self::Tick>;
+
+    /// The SIP timestamp of a tick.
+    fn timestamp(tick: &Self::Tick) -> DateTime<Utc>;
+}
+
+impl TickRequest for GetQuotes {
+    type Tick = Quote;
+
+    fn for_day(
+        ticker: &Ticker,
+        date: NaiveDate,
+        from: DateTime<Utc>,
+        limit_to: Option<DateTime<Utc>>,
+        limit: u32,
+    ) -> Self {
+        let req = GetQuotes::new(ticker, date).timestamp(from).limit(limit);
+        match limit_to {
+            Some(to) => req.timestamp_limit(to),
+            None => req,
+        }
+    }
+
+    fn into_ticks(response: QuoteWrapper) -> Vec<Quote> {
+        response.results
+    }
+
+    fn timestamp(tick: &Quote) -> DateTime<Utc> {
+        tick.t
+    }
+}
+
+impl TickRequest for GetTrades {
+    type Tick = Trade;
+
+    fn for_day(
+        ticker: &Ticker,
+        date: NaiveDate,
+        from: DateTime<Utc>,
+        limit_to: Option<DateTime<Utc>>,
+        limit: u32,
+    ) -> Self {
+        let req = GetTrades::new(ticker, date).timestamp(from).limit(limit);
+        match limit_to {
+            Some(to) => req.timestamp_limit(to),
+            None => req,
+        }
+    }
+
+    fn into_ticks(response: TradeWrapper) -> Vec<Trade> {
+        response.results
+    }
+
+    fn timestamp(tick: &Trade) -> DateTime<Utc> {
+        tick.t
+    }
+}
+
+/// The order in which the days of a [`GetTickRange`] are yielded.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TickOrder {
+    /// Days are yielded in calendar order, so ticks are in timestamp order. A slow day holds back
+    /// the days after it, whose pages are buffered until it has been yielded.
+    Chronological,
+    /// Days are yielded as soon as they have been fetched. Ticks within a day are still in
+    /// timestamp order.
+    Completion,
+}
+
+/// Request the ticks for a ticker between two timestamps, which may span many trading days.
+///
+/// Polygon serves ticks one Eastern trading day at a time, so the range is split into a
+/// [`TickRequest`] per day, skipping weekends and the given holidays. Days are fetched
+/// `concurrency` at a time, and their pages are yielded as they arrive, except that pages of
+/// later days are held back in [`TickOrder::Chronological`] order.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GetTickRange<R> {
+    ticker: Ticker,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    holidays: Vec<NaiveDate>,
+    limit: u32,
+    concurrency: usize,
+    order: TickOrder,
+    #[serde(skip)]
+    kind: PhantomData<R>,
+}
+
+/// Request the NBBO quotes for a ticker over a range of days.
+pub type GetQuotesRange = GetTickRange<GetQuotes>;
+/// Request the trades for a ticker over a range of days.
+pub type GetTradesRange = GetTickRange<GetTrades>;
+
+impl<R: TickRequest> GetTickRange<R> {
+    /// Ticks from `from` up to, but excluding, `to`.
+    pub fn new<T: Into<Ticker>>(ticker: T, from: DateTime<Utc>, to: DateTime<Utc>) -> Self {
+        Self {
+            ticker: ticker.into(),
+            from,
+            to,
+            holidays: Vec::new(),
+            limit: 50000,
+            concurrency: 1,
+            order: TickOrder::Chronological,
+            kind: PhantomData,
+        }
+    }
+
+    /// Days on which the market is closed besides weekends, such as the closed days returned by
+    /// [`GetMarketHolidays`](super::GetMarketHolidays). Other days without ticks still cost a
+    /// request.
+    pub fn holidays<I: IntoIterator<Item = NaiveDate>>(mut self, holidays: I) -> Self {
+        self.holidays.extend(holidays);
+        self
+    }
+
+    /// The number of ticks requested per page.
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// The number of days fetched at once. Requests are still subject to the rate limit of the
+    /// client.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn order(mut self, order: TickOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// The Eastern trading days that overlap the range.
+    pub fn trading_days(&self) -> Vec<NaiveDate> {
+        if self.to <= self.from {
+            return Vec::new();
+        }
+        let first = self.from.with_timezone(&Eastern).date_naive();
+        let last = (self.to - Duration::nanoseconds(1))
+            .with_timezone(&Eastern)
+            .date_naive();
+        first
+            .iter_days()
+            .take_while(|date| *date <= last)
+            .filter(|date| !matches!(date.weekday(), Weekday::Sat | Weekday::Sun))
+            .filter(|date| !self.holidays.contains(date))
+            .collect()
+    }
+}
+
+impl ThrottledClient {
+    /// Stream the ticks of a [`GetTickRange`], following the pagination of every day. An error
+    /// fetching a day is yielded in place of its ticks, and the remaining days are still fetched.
+    pub fn send_tick_range<'a, R>(
+        &'a self,
+        request: &GetTickRange<R>,
+    ) -> impl Stream<Item = Result<R::Tick>> + Unpin + 'a
+    where
+        R: TickRequest + 'a,
+        R::Tick: 'a,
+    {
+        let ticker = request.ticker.clone();
+        let (from, to, limit) = (request.from, request.to, request.limit);
+        let days = request.trading_days();
+        let last = days.last().copied();
+        let days = days.into_iter().map(move |date| {
+            let limit_to = (Some(date) == last).then_some(to);
+            let req = R::for_day(&ticker, date, from, limit_to, limit);
+            let pages = self.into_paginated(req).map_ok(move |page| {
+                R::into_ticks(page)
+                    .into_iter()
+                    .filter(|tick| (from..to).contains(&R::timestamp(tick)))
+                    .collect::<Vec<_>>()
+            });
+            Box::pin(pages) as Pin<Box<dyn Stream<Item = Result<Vec<R::Tick>>> + 'a>>
+        });
+        let pages = DayPages {
+            days,
+            active: VecDeque::new(),
+            concurrency: request.concurrency,
+            order: request.order,
+        };
+        Box::pin(
+            pages
+                .map_ok(|ticks| stream::iter(ticks.into_iter().map(Ok)))
+                .try_flatten(),
+        )
+    }
+}
+
+/// A day of a tick range that is being fetched.
+struct ActiveDay<S: Stream> {
+    /// The remaining pages, or `None` once the day has been fetched.
+    pages: Option<S>,
+    buffered: VecDeque<S::Item>,
+}
+
+/// The pages of the days of a tick range, fetched `concurrency` days at a time. In chronological
+/// order only the first day's pages are yielded, and the pages of the days after it are
+/// buffered until it is done.
+struct DayPages<I: Iterator>
+where
+    I::Item: Stream,
+{
+    days: I,
+    active: VecDeque<ActiveDay<I::Item>>,
+    concurrency: usize,
+    order: TickOrder,
+}
+
+// Pages are never pinned, so buffering them doesn't require them to be `Unpin`
+impl<I: Iterator + Unpin> Unpin for DayPages<I> where I::Item: Stream + Unpin {}
+
+impl<I> Stream for DayPages<I>
+where
+    I: Iterator + Unpin,
+    I::Item: Stream + Unpin,
+{
+    type Item = <I::Item as Stream>::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            while this.active.len() < this.concurrency {
+                match this.days.next() {
+                    Some(pages) => this.active.push_back(ActiveDay {
+                        pages: Some(pages),
+                        buffered: VecDeque::new(),
+                    }),
+                    None => break,
+                }
+            }
+            if this.active.is_empty() {
+                return Poll::Ready(None);
+            }
+            // Later days are polled first, so that they make progress while the first day has
+            // pages ready
+            let mut finished = None;
+            for (i, day) in this.active.iter_mut().enumerate().rev() {
+                let yields = i == 0 || this.order == TickOrder::Completion;
+                if yields {
+                    if let Some(page) = day.buffered.pop_front() {
+                        return Poll::Ready(Some(page));
+                    }
+                }
+                while let Some(pages) = day.pages.as_mut() {
+                    match pages.poll_next_unpin(cx) {
+                        Poll::Ready(Some(page)) if yields => return Poll::Ready(Some(page)),
+                        Poll::Ready(Some(page)) => day.buffered.push_back(page),
+                        Poll::Ready(None) => day.pages = None,
+                        Poll::Pending => break,
+                    }
+                }
+                if day.pages.is_none() && day.buffered.is_empty() {
+                    finished = Some(i);
+                }
+            }
+            match finished {
+                Some(i) => {
+                    this.active.remove(i);
+                }
+                None => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::TimeZone;
+    use mockito::{mock, Matcher};
+
+    fn trade(t: i64) -> String {
+        format!(
+            r#"{{"t":{},"y":{},"q":1,"i":"1","x":4,"s":100,"c":[12],"p":120.5,"z":3}}"#,
+            t, t
+        )
+    }
+
+    #[test]
+    fn trading_days_skip_weekends_and_holidays() {
+        // 2021-04-01 23:00 in New York is already 2021-04-02 in UTC
+        let from = Utc.with_ymd_and_hms(2021, 4, 2, 3, 0, 0).unwrap();
+        let to = Utc.with_ymd_and_hms(2021, 4, 7, 4, 0, 0).unwrap();
+        let req = GetTradesRange::new("AAPL", from, to)
+            .holidays(vec![NaiveDate::from_ymd_opt(2021, 4, 2).unwrap()]);
+        let days: Vec<String> = req.trading_days().iter().map(|d| d.to_string()).collect();
+        assert_eq!(days, vec!["2021-04-01", "2021-04-05", "2021-04-06"]);
+    }
+
+    #[tokio::test]
+    async fn stitches_days_in_order() {
+        let start = Utc.with_ymd_and_hms(2021, 6, 1, 14, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2021, 6, 2, 20, 0, 0).unwrap();
+        let before = (start - Duration::minutes(1))
+            .timestamp_nanos_opt()
+            .unwrap();
+        let first = start.timestamp_nanos_opt().unwrap();
+        let second = (end - Duration::hours(1)).timestamp_nanos_opt().unwrap();
+        let after = end.timestamp_nanos_opt().unwrap();
+        let _day1 = mock("GET", "/v2/ticks/stocks/trades/AAPL/2021-06-01")
+            .match_query(Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()))
+            .with_body(format!(
+                r#"{{"ticker":"AAPL","success":true,"results_count":2,"db_latency":1,"results":[{},{}]}}"#,
+                trade(before),
+                trade(first)
+            ))
+            .create();
+        let _day2 = mock("GET", "/v2/ticks/stocks/trades/AAPL/2021-06-02")
+            .match_query(Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()))
+            .with_body(format!(
+                r#"{{"ticker":"AAPL","success":true,"results_count":2,"db_latency":1,"results":[{},{}]}}"#,
+                trade(second),
+                trade(after)
+            ))
+            .create();
+
+        let client = ThrottledClient::builder("TOKEN")
+            .base_url(mockito::server_url())
+            .build()
+            .unwrap();
+        let req = GetTradesRange::new("AAPL", start, end).concurrency(2);
+        let trades: Vec<Trade> = client.send_tick_range(&req).try_collect().await.unwrap();
+        let stamps: Vec<i64> = trades
+            .iter()
+            .map(|t| t.t.timestamp_nanos_opt().unwrap())
+            .collect();
+        assert_eq!(stamps, vec![first, second]);
+    }
+
+    #[tokio::test]
+    async fn streams_pages_of_each_day() {
+        let start = Utc.with_ymd_and_hms(2021, 6, 1, 14, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2021, 6, 2, 20, 0, 0).unwrap();
+        let stamps: Vec<i64> = [0, 1, 2, 25]
+            .iter()
+            .map(|h| (start + Duration::hours(*h)).timestamp_nanos_opt().unwrap())
+            .collect();
+        let body = |trades: &[i64]| {
+            let results: Vec<String> = trades.iter().map(|t| trade(*t)).collect();
+            format!(
+                r#"{{"ticker":"MSFT","success":true,"results_count":{},"db_latency":1,"results":[{}]}}"#,
+                trades.len(),
+                results.join(",")
+            )
+        };
+        let _page1 = mock("GET", "/v2/ticks/stocks/trades/MSFT/2021-06-01")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()),
+                Matcher::UrlEncoded("timestamp".into(), stamps[0].to_string()),
+            ]))
+            .with_body(body(&stamps[..2]))
+            .create();
+        let _page2 = mock("GET", "/v2/ticks/stocks/trades/MSFT/2021-06-01")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()),
+                Matcher::UrlEncoded("timestamp".into(), stamps[1].to_string()),
+            ]))
+            .with_body(body(&stamps[2..3]))
+            .create();
+        // Only the last day is limited to the end of the range
+        let _day2 = mock("GET", "/v2/ticks/stocks/trades/MSFT/2021-06-02")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("apiKey".into(), "TOKEN".into()),
+                Matcher::UrlEncoded(
+                    "timestampLimit".into(),
+                    end.timestamp_nanos_opt().unwrap().to_string(),
+                ),
+            ]))
+            .with_body(body(&stamps[3..]))
+            .create();
+
+        let client = ThrottledClient::builder("TOKEN")
+            .base_url(mockito::server_url())
+            .build()
+            .unwrap();
+        let req = GetTradesRange::new("MSFT", start, end)
+            .limit(2)
+            .concurrency(2);
+        let trades: Vec<Trade> = client.send_tick_range(&req).try_collect().await.unwrap();
+        let got: Vec<i64> = trades
+            .iter()
+            .map(|t| t.t.timestamp_nanos_opt().unwrap())
+            .collect();
+        assert_eq!(got, stamps);
+    }
+}
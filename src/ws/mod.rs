@@ -5,6 +5,7 @@ use std::borrow::Cow;
 use std::collections::VecDeque;
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::time::Duration;
 use tokio_tungstenite::connect_async;
 use tokio_tungstenite::tungstenite::Message;
 use tracing::info;
@@ -12,7 +13,11 @@ use tracing::info;
 pub mod types;
 pub use types::*;
 
-type TungsteniteResult = std::result::Result<Message, tokio_tungstenite::tungstenite::Error>;
+pub(crate) type TungsteniteResult =
+    std::result::Result<Message, tokio_tungstenite::tungstenite::Error>;
+
+type Socket =
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
 
 pub struct WebSocket<T> {
     inner: T,
@@ -55,7 +60,7 @@ impl<T: Stream<Item = TungsteniteResult> + Unpin> Stream for WebSocket<T> {
                 cx.waker().wake_by_ref();
                 Poll::Pending
             }
-            Some(Err(e)) => Poll::Ready(Some(Err(Error::Tungstenite(e)))),
+            Some(Err(e)) => Poll::Ready(Some(Err(e.into()))),
             None => Poll::Ready(None),
         }
     }
@@ -111,32 +116,82 @@ impl<T: Sink<Message> + Unpin> WebSocket<T> {
     }
 }
 
-pub struct Connection<'a> {
-    url: &'a str,
-    auth_token: &'a str,
-    events: &'a [&'a str],
-    assets: &'a [&'a str],
+/// The messages of a connected WebSocket, without exposing the WebSocket implementation.
+pub struct MessageStream(WebSocket<Socket>);
+
+impl MessageStream {
+    /// Subscribe to more events for more assets.
+    pub async fn subscribe(&mut self, events: &[&str], assets: &[&str]) -> Result<()> {
+        self.0.subscribe(events, assets).await
+    }
 }
 
-impl<'a> Connection<'a> {
-    pub fn new(
-        url: &'a str,
-        auth_token: &'a str,
-        events: &'a [&'a str],
-        assets: &'a [&'a str],
-    ) -> Self {
+impl Stream for MessageStream {
+    type Item = Result<PolygonMessage>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.0).poll_next(cx)
+    }
+}
+
+#[derive(Clone)]
+pub struct Connection {
+    url: String,
+    auth_token: String,
+    events: Vec<String>,
+    assets: Vec<String>,
+    connect_timeout: Option<Duration>,
+}
+
+impl Connection {
+    pub fn new<U, T, E, A>(url: U, auth_token: T, events: &[E], assets: &[A]) -> Self
+    where
+        U: ToString,
+        T: ToString,
+        E: ToString,
+        A: ToString,
+    {
         Self {
-            url,
-            auth_token,
-            events,
-            assets,
+            url: url.to_string(),
+            auth_token: auth_token.to_string(),
+            events: events.iter().map(|e| e.to_string()).collect(),
+            assets: assets.iter().map(|a| a.to_string()).collect(),
+            connect_timeout: None,
         }
     }
 
+    /// Fail with [`Error::ConnectionFailure`] if the WebSocket handshake takes longer than
+    /// `timeout`.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Connect and subscribe, returning the socket itself.
+    #[deprecated(
+        note = "use `Connection::messages`, which doesn't expose the WebSocket implementation"
+    )]
     pub async fn connect(
         self,
     ) -> Result<WebSocket<impl Stream<Item = TungsteniteResult> + Sink<Message> + Unpin>> {
-        let (client, _) = connect_async(self.url).await?;
+        self.open().await
+    }
+
+    /// Connect, authenticate and subscribe to the events for the assets, returning the stream of
+    /// messages.
+    pub async fn messages(self) -> Result<MessageStream> {
+        self.open().await.map(MessageStream)
+    }
+
+    async fn open(self) -> Result<WebSocket<Socket>> {
+        let (client, _) = match self.connect_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, connect_async(self.url.as_str()))
+                .await
+                .map_err(|_| {
+                    Error::ConnectionFailure(format!("Timed out connecting to {}", self.url))
+                })??,
+            None => connect_async(self.url.as_str()).await?,
+        };
         let mut ws = WebSocket {
             inner: client,
             buffer: VecDeque::new(),
@@ -151,7 +206,7 @@ impl<'a> Connection<'a> {
         }
         let auth_message = PolygonAction {
             action: "auth".into(),
-            params: Cow::Owned(self.auth_token.to_string()),
+            params: Cow::Owned(self.auth_token),
         };
         ws.send(&serde_json::to_string(&auth_message).map_err(|_| Error::Serialize(auth_message))?)
             .await?;
@@ -163,7 +218,9 @@ impl<'a> Connection<'a> {
                 return Err(Error::ConnectionFailure(message));
             }
         }
-        ws.subscribe(self.events, self.assets).await?;
+        let events: Vec<&str> = self.events.iter().map(|e| e.as_str()).collect();
+        let assets: Vec<&str> = self.assets.iter().map(|a| a.as_str()).collect();
+        ws.subscribe(&events, &assets).await?;
         Ok(ws)
     }
 }
@@ -226,6 +283,7 @@ mod test {
     }
 
     #[tokio::test]
+    #[allow(deprecated)]
     async fn test_connection() {
         let (con_tx, con_rx) = futures_channel::oneshot::channel();
         tokio::spawn(async move {
@@ -1,3 +1,4 @@
+use crate::common::Ticker;
 use chrono::{serde::ts_milliseconds, DateTime, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
@@ -5,7 +6,7 @@ use serde::{Deserialize, Serialize};
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Aggregate {
     #[serde(rename = "sym")]
-    pub symbol: String,
+    pub symbol: Ticker,
     #[serde(rename = "v")]
     pub volume: u32,
     #[serde(rename = "av")]